@@ -0,0 +1,24 @@
+#![no_main]
+
+use account_transaction_reader::{data_structures::Transaction, services::AccountService};
+use libfuzzer_sys::fuzz_target;
+
+// Feeds arbitrary bytes through the same csv reading and recording path as the CLI. Rows that
+// fail to parse are skipped like in the CLI, anything that panics is a bug.
+fuzz_target!(|data: &[u8]| {
+    let mut account_service = AccountService::new();
+    let mut transactions_reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(data);
+
+    for transaction in transactions_reader
+        .deserialize::<Transaction>()
+        .filter_map(Result::ok)
+    {
+        let _ = account_service.record_transaction(transaction);
+    }
+
+    for account in account_service.summary().values() {
+        let _ = account.available + account.held;
+    }
+});
@@ -1,8 +1,53 @@
 use rust_decimal::Decimal;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+use super::{AccountActivity, precision, safe_ratio};
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Account {
+    pub client: u16,
+    #[serde(
+        serialize_with = "super::utils::serde::high_precision_decimal::serialize",
+        deserialize_with = "super::utils::serde::high_precision_decimal::deserialize_formatted"
+    )]
+    pub available: Decimal,
+    #[serde(
+        serialize_with = "super::utils::serde::high_precision_decimal::serialize",
+        deserialize_with = "super::utils::serde::high_precision_decimal::deserialize_formatted"
+    )]
+    pub held: Decimal,
+    #[serde(
+        serialize_with = "super::utils::serde::high_precision_decimal::serialize",
+        deserialize_with = "super::utils::serde::high_precision_decimal::deserialize_formatted"
+    )]
+    pub total: Decimal,
+    pub locked: bool,
+}
+
+/// The optional columns of the summary output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SummaryColumns {
+    /// The totals of the deposits and withdrawals of a client
+    pub wide: bool,
+    /// The ids of the transactions of a client that are under dispute at the moment
+    pub open_disputes: bool,
+    /// The share of the total funds that is held, see [`frozen_ratio`]
+    pub frozen_ratio: bool,
+}
+
+/// `held / total` rounded to the configured precision, or 0 if there are no funds at all. Unlike
+/// the amounts the ratio always has all fractional digits, e.g. `0.3000` by default.
+pub fn frozen_ratio(account: &Account) -> String {
+    let precision = precision();
+    let mut ratio = safe_ratio(account.held, account.total).round_dp(precision);
+    ratio.rescale(precision);
+    ratio.to_string()
+}
+
+/// An account along with the optional columns of the summary. Columns that weren't selected are
+/// left out of the output entirely.
+#[derive(Debug, Serialize)]
+pub struct WideAccount {
     pub client: u16,
     #[serde(with = "super::utils::serde::high_precision_decimal")]
     pub available: Decimal,
@@ -11,4 +56,64 @@ pub struct Account {
     #[serde(with = "super::utils::serde::high_precision_decimal")]
     pub total: Decimal,
     pub locked: bool,
+    #[serde(
+        with = "super::utils::serde::high_precision_decimal_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub deposited: Option<Decimal>,
+    #[serde(
+        with = "super::utils::serde::high_precision_decimal_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub withdrawn: Option<Decimal>,
+    /// Semicolon separated, empty if nothing is under dispute
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_dispute_txs: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frozen_ratio: Option<String>,
+}
+
+impl WideAccount {
+    pub fn new(
+        account: &Account,
+        columns: SummaryColumns,
+        activity: Option<&AccountActivity>,
+        open_dispute_txs: &[u32],
+    ) -> Self {
+        let activity = activity.cloned().unwrap_or_default();
+        Self {
+            client: account.client,
+            available: account.available,
+            held: account.held,
+            total: account.total,
+            locked: account.locked,
+            deposited: columns.wide.then_some(activity.deposited),
+            withdrawn: columns.wide.then_some(activity.withdrawn),
+            open_dispute_txs: columns.open_disputes.then(|| {
+                open_dispute_txs
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(";")
+            }),
+            frozen_ratio: columns.frozen_ratio.then(|| frozen_ratio(account)),
+        }
+    }
+}
+
+/// A row of the summary output.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum SummaryRow<'a> {
+    Standard(&'a Account),
+    Wide(WideAccount),
+}
+
+impl SummaryRow<'_> {
+    pub fn client(&self) -> u16 {
+        match self {
+            Self::Standard(account) => account.client,
+            Self::Wide(account) => account.client,
+        }
+    }
 }
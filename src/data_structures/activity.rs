@@ -0,0 +1,19 @@
+use rust_decimal::Decimal;
+
+/// Aggregates about what happened to an account during the run which aren't part of its balance.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountActivity {
+    /// Sum of all applied deposits
+    pub deposited: Decimal,
+    /// Sum of all applied withdrawals
+    pub withdrawn: Decimal,
+    /// Number of applied disputes, a transaction disputed again after a resolve counts twice
+    pub disputes: usize,
+    /// Sum of the amounts that were put under dispute, regardless of how the disputes ended
+    pub disputed_volume: Decimal,
+    /// Deposited funds that went to covering a negative available balance, e.g. after a deposit
+    /// got disputed when most of it was already withdrawn
+    pub overdraft_covered: Decimal,
+    /// The transaction whose chargeback locked the account
+    pub locked_by: Option<u32>,
+}
@@ -0,0 +1,106 @@
+use crate::data_structures::TransactionType;
+
+/// Where a disputable transaction is in the dispute flow. The allowed moves are
+///
+/// - `None` -> `Open` on a dispute
+/// - `Open` -> `Resolved` on a resolve
+/// - `Open` -> `ChargedBack` on a chargeback
+/// - `Resolved` -> `Open` on a dispute, the client disputes the transaction again
+/// - `Resolved` -> `ChargedBack` on a chargeback, the resolve is undone and the chargeback applied
+///
+/// `ChargedBack` is final.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisputeState {
+    /// The transaction has never been disputed
+    #[default]
+    None,
+    Open,
+    Resolved,
+    ChargedBack,
+}
+
+impl DisputeState {
+    /// The state after applying a dispute, resolve or chargeback, or `None` if the move isn't
+    /// allowed from the current state.
+    pub fn transition(self, r#type: &TransactionType) -> Option<Self> {
+        match (self, r#type) {
+            (Self::None | Self::Resolved, TransactionType::Dispute) => Some(Self::Open),
+            (Self::Open, TransactionType::Resolve) => Some(Self::Resolved),
+            (Self::Open | Self::Resolved, TransactionType::Chargeback) => Some(Self::ChargedBack),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_transitions() {
+        assert_eq!(
+            DisputeState::None.transition(&TransactionType::Dispute),
+            Some(DisputeState::Open)
+        );
+        assert_eq!(
+            DisputeState::Open.transition(&TransactionType::Resolve),
+            Some(DisputeState::Resolved)
+        );
+        assert_eq!(
+            DisputeState::Open.transition(&TransactionType::Chargeback),
+            Some(DisputeState::ChargedBack)
+        );
+        assert_eq!(
+            DisputeState::Resolved.transition(&TransactionType::Dispute),
+            Some(DisputeState::Open)
+        );
+        assert_eq!(
+            DisputeState::Resolved.transition(&TransactionType::Chargeback),
+            Some(DisputeState::ChargedBack)
+        );
+    }
+
+    #[test]
+    fn test_invalid_transitions() {
+        assert_eq!(
+            DisputeState::None.transition(&TransactionType::Resolve),
+            None
+        );
+        assert_eq!(
+            DisputeState::None.transition(&TransactionType::Chargeback),
+            None
+        );
+        assert_eq!(
+            DisputeState::Open.transition(&TransactionType::Dispute),
+            None
+        );
+        assert_eq!(
+            DisputeState::Resolved.transition(&TransactionType::Resolve),
+            None
+        );
+        for r#type in [
+            TransactionType::Dispute,
+            TransactionType::Resolve,
+            TransactionType::Chargeback,
+        ] {
+            assert_eq!(DisputeState::ChargedBack.transition(&r#type), None);
+        }
+    }
+
+    #[test]
+    fn test_non_dispute_types_never_transition() {
+        for state in [
+            DisputeState::None,
+            DisputeState::Open,
+            DisputeState::Resolved,
+            DisputeState::ChargedBack,
+        ] {
+            assert_eq!(state.transition(&TransactionType::Deposit), None);
+            assert_eq!(state.transition(&TransactionType::Withdrawal), None);
+            assert_eq!(
+                state.transition(&TransactionType::Unknown("refund".to_string())),
+                None
+            );
+        }
+    }
+}
@@ -1,5 +1,11 @@
 mod account;
+mod activity;
+mod dispute;
 mod transaction;
 mod utils;
 pub use account::*;
+pub use activity::*;
+pub use dispute::*;
 pub use transaction::*;
+pub use utils::format::*;
+pub use utils::math::*;
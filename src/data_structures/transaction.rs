@@ -1,7 +1,7 @@
 use rust_decimal::Decimal;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum TransactionType {
     Deposit,
@@ -9,15 +9,40 @@ pub enum TransactionType {
     Dispute,
     Resolve,
     Chargeback,
+    /// Clears the lock a chargeback put on the account, e.g. when the payment processor reversed
+    /// the chargeback
+    Unlock,
     #[serde(untagged)]
     Unknown(String),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+// deserialized from the raw string, since the csv deserializer would otherwise infer numeric
+// tokens like `1` as integers which can't be kept as an `Unknown` type. the known types match
+// regardless of their casing and always serialize as lowercase, unknown ones keep the raw token
+impl<'de> Deserialize<'de> for TransactionType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let token = String::deserialize(deserializer)?;
+        Ok(match token.to_ascii_lowercase().as_str() {
+            "deposit" => Self::Deposit,
+            "withdrawal" => Self::Withdrawal,
+            "dispute" => Self::Dispute,
+            "resolve" => Self::Resolve,
+            "chargeback" => Self::Chargeback,
+            "unlock" => Self::Unlock,
+            _ => Self::Unknown(token),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Transaction {
     pub r#type: TransactionType,
     pub client: u16,
     pub tx: u32,
     #[serde(default, with = "super::utils::serde::high_precision_decimal_option")]
     pub amount: Option<Decimal>,
+    /// Optional ordering key, e.g. a unix timestamp. Only used to order the transactions under
+    /// `--sort-by-timestamp`, so it isn't part of the canonical output.
+    #[serde(default, skip_serializing)]
+    pub timestamp: Option<i64>,
 }
@@ -0,0 +1,71 @@
+use std::cell::Cell;
+
+use clap::ValueEnum;
+use rust_decimal::Decimal;
+
+/// How negative amounts are written in the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum NegativeStyle {
+    /// `-10.5`
+    #[default]
+    Minus,
+    /// `(10.5)`, the accounting style
+    Parens,
+}
+
+// serde `with` functions can't take parameters so the output formatting is configured per thread
+// before serializing
+thread_local! {
+    static NEGATIVE_STYLE: Cell<NegativeStyle> = const { Cell::new(NegativeStyle::Minus) };
+    static PRECISION: Cell<u32> = const { Cell::new(DEFAULT_PRECISION) };
+}
+
+/// Number of fractional digits amounts are read and written with unless configured otherwise.
+pub const DEFAULT_PRECISION: u32 = 4;
+/// Most fractional digits a `Decimal` can hold.
+pub const MAX_PRECISION: u32 = 28;
+
+pub fn set_negative_style(style: NegativeStyle) {
+    NEGATIVE_STYLE.with(|cell| cell.set(style));
+}
+
+pub fn negative_style() -> NegativeStyle {
+    NEGATIVE_STYLE.with(Cell::get)
+}
+
+/// Set the number of fractional digits amounts are rounded to when they're read and written,
+/// at most [`MAX_PRECISION`].
+pub fn set_precision(precision: u32) {
+    PRECISION.with(|cell| cell.set(precision.min(MAX_PRECISION)));
+}
+
+pub fn precision() -> u32 {
+    PRECISION.with(Cell::get)
+}
+
+/// Format an amount with up to the configured number of fractional digits, 4 by default, the way
+/// it's written in the output.
+pub fn format_amount(decimal: &Decimal) -> String {
+    let precision = precision() as usize;
+    if negative_style() == NegativeStyle::Parens && decimal.is_sign_negative() {
+        let formatted_decimal = format!("{:.precision$}", decimal.abs());
+        let trimmed = trim_fraction(&formatted_decimal);
+        if trimmed != "0" {
+            return format!("({trimmed})");
+        }
+    }
+
+    let formatted_decimal = format!("{:.precision$}", decimal);
+    trim_fraction(&formatted_decimal).to_string()
+}
+
+/// Remove trailing zeros after the decimal point, along with the point if nothing is left of the
+/// fraction.
+fn trim_fraction(formatted_decimal: &str) -> &str {
+    if !formatted_decimal.contains('.') {
+        return formatted_decimal;
+    }
+    formatted_decimal
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+}
@@ -0,0 +1,56 @@
+use rust_decimal::Decimal;
+
+/// `numerator / denominator` for reporting, where a ratio of nothing is 0. Decimals have no NaN
+/// or infinity, so a zero denominator or a quotient that can't be represented yields 0 instead of
+/// panicking.
+pub fn safe_ratio(numerator: Decimal, denominator: Decimal) -> Decimal {
+    numerator.checked_div(denominator).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{Account, DEFAULT_PRECISION, frozen_ratio, set_precision};
+
+    #[test]
+    fn test_safe_ratio() {
+        assert_eq!(
+            safe_ratio(Decimal::from(30), Decimal::from(100)),
+            Decimal::new(3, 1)
+        );
+        assert_eq!(safe_ratio(Decimal::from(30), Decimal::ZERO), Decimal::ZERO);
+        assert_eq!(safe_ratio(Decimal::MAX, Decimal::new(1, 4)), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_frozen_ratio_of_empty_account() {
+        let account = Account {
+            client: 1,
+            available: Decimal::ZERO,
+            held: Decimal::ZERO,
+            total: Decimal::ZERO,
+            locked: false,
+        };
+        assert_eq!(frozen_ratio(&account), "0.0000");
+    }
+
+    #[test]
+    fn test_frozen_ratio_follows_precision() {
+        let account = Account {
+            client: 1,
+            available: Decimal::from(2),
+            held: Decimal::from(1),
+            total: Decimal::from(3),
+            locked: false,
+        };
+        set_precision(2);
+        let ratio = frozen_ratio(&account);
+        set_precision(6);
+        let precise_ratio = frozen_ratio(&account);
+        set_precision(DEFAULT_PRECISION);
+
+        assert_eq!(ratio, "0.33");
+        assert_eq!(precise_ratio, "0.333333");
+        assert_eq!(frozen_ratio(&account), "0.3333");
+    }
+}
@@ -1 +1,3 @@
+pub mod format;
+pub mod math;
 pub mod serde;
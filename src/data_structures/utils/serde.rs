@@ -1,26 +1,70 @@
+// Decimals are (de)serialized through these helpers on purpose instead of rust_decimal's serde
+// features. The written amounts are a contract with whoever consumes the summary, so their format
+// must not change because a rust_decimal release serializes differently.
 pub mod high_precision_decimal {
     use std::str::FromStr;
 
     use rust_decimal::Decimal;
     use serde::{Deserialize, Deserializer, Serializer};
 
+    use crate::data_structures::utils::format::{format_amount, precision};
+
     pub fn serialize<S: Serializer>(decimal: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
-        let formatted_decimal = format!("{:.4}", decimal);
-        // Remove trailing zeros after decimal point
-        let trimmed = formatted_decimal
-            .trim_end_matches('0')
-            .trim_end_matches('.');
-        serializer.serialize_str(trimmed)
+        serializer.serialize_str(&format_amount(decimal))
     }
 
     pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
         let string = String::deserialize(deserializer)?;
-        let decimal = Decimal::from_str(&string).map_err(serde::de::Error::custom)?;
-        // Limit to 4 fractional digits
-        let limited_decimal = decimal.round_dp(4);
+        // surrounding whitespace is up to the reader's trimming, `Decimal` would silently skip it
+        if string.trim() != string {
+            return Err(serde::de::Error::custom(format!(
+                "amount '{string}' has surrounding whitespace"
+            )));
+        }
+        // some feeds mark credits explicitly with a leading `+`, which isn't a sign of its own
+        let unsigned = match string.strip_prefix('+') {
+            Some(unsigned) if !unsigned.starts_with(['+', '-']) => unsigned,
+            _ => &string,
+        };
+        let decimal = Decimal::from_str(unsigned).map_err(|err| match err {
+            // `Decimal` holds at most 28-29 significant digits, anything beyond isn't a parse error
+            // on the partners side but a value we can't represent
+            rust_decimal::Error::ExceedsMaximumPossibleValue
+            | rust_decimal::Error::LessThanMinimumPossibleValue => exceeds_precision(&string),
+            rust_decimal::Error::ErrorString(message) if message.contains("overflow") => {
+                exceeds_precision(&string)
+            }
+            err => serde::de::Error::custom(err),
+        })?;
+        // Limit to the configured fractional digits, 4 by default
+        let limited_decimal = decimal.round_dp(precision());
 
         Ok(limited_decimal)
     }
+
+    /// Deserialize an amount the way it's written in the output, which can be in the accounting
+    /// style depending on the negative style.
+    pub fn deserialize_formatted<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Decimal, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        match string
+            .strip_prefix('(')
+            .and_then(|string| string.strip_suffix(')'))
+        {
+            Some(negated) => {
+                deserialize(serde::de::value::StrDeserializer::<D::Error>::new(negated))
+                    .map(|decimal| -decimal)
+            }
+            None => deserialize(serde::de::value::StringDeserializer::<D::Error>::new(
+                string,
+            )),
+        }
+    }
+
+    fn exceeds_precision<E: serde::de::Error>(amount: &str) -> E {
+        E::custom(format!("amount '{amount}' exceeds supported precision"))
+    }
 }
 
 pub mod high_precision_decimal_option {
@@ -34,7 +78,7 @@ pub mod high_precision_decimal_option {
         serializer: S,
     ) -> Result<S::Ok, S::Error> {
         if let Some(decimal) = decimal_opt {
-            high_precision_decimal::serialize(&decimal, serializer)
+            high_precision_decimal::serialize(decimal, serializer)
         } else {
             serializer.serialize_none()
         }
@@ -55,3 +99,146 @@ pub mod high_precision_decimal_option {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use crate::data_structures::{
+        Account, DEFAULT_PRECISION, NegativeStyle, Transaction, set_negative_style, set_precision,
+    };
+
+    fn serialize_account(account: &Account) -> String {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(vec![]);
+        writer.serialize(account).unwrap();
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_negative_style() {
+        let account = Account {
+            client: 1,
+            available: Decimal::new(-105, 1),
+            held: Decimal::new(105, 1),
+            total: Decimal::ZERO,
+            locked: false,
+        };
+
+        assert_eq!(serialize_account(&account), "1,-10.5,10.5,0,false\n");
+
+        set_negative_style(NegativeStyle::Parens);
+        let serialized = serialize_account(&account);
+        set_negative_style(NegativeStyle::Minus);
+        assert_eq!(serialized, "1,(10.5),10.5,0,false\n");
+    }
+
+    #[test]
+    fn test_pinned_amount_format() {
+        let cases = [
+            (Decimal::ZERO, "0"),
+            (Decimal::new(-0, 4), "0"),
+            (Decimal::ONE, "1"),
+            (Decimal::from(100), "100"),
+            (Decimal::new(105000, 4), "10.5"),
+            (Decimal::new(-105, 1), "-10.5"),
+            (Decimal::new(1, 4), "0.0001"),
+            (Decimal::new(-1, 4), "-0.0001"),
+            (Decimal::new(12345, 4), "1.2345"),
+            (Decimal::new(-99999999, 4), "-9999.9999"),
+        ];
+
+        for (amount, expected) in cases {
+            let account = Account {
+                client: 1,
+                available: amount,
+                held: Decimal::ZERO,
+                total: Decimal::ZERO,
+                locked: false,
+            };
+            assert_eq!(
+                serialize_account(&account),
+                format!("1,{expected},0,0,false\n"),
+                "amount {amount:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_precision() {
+        let read_amount = || {
+            csv::ReaderBuilder::new()
+                .from_reader("type,client,tx,amount\ndeposit,1,1,1.123456789\n".as_bytes())
+                .deserialize::<Transaction>()
+                .next()
+                .unwrap()
+                .unwrap()
+                .amount
+                .unwrap()
+        };
+        let account = |available| Account {
+            client: 1,
+            available,
+            held: Decimal::new(10, 0),
+            total: Decimal::ZERO,
+            locked: false,
+        };
+
+        set_precision(2);
+        let amount_2 = read_amount();
+        let serialized_2 = serialize_account(&account(Decimal::new(-1004, 3)));
+        set_precision(8);
+        let amount_8 = read_amount();
+        let serialized_8 = serialize_account(&account(Decimal::new(112345679, 8)));
+        set_precision(0);
+        let serialized_0 = serialize_account(&account(Decimal::new(104, 1)));
+        set_precision(DEFAULT_PRECISION);
+
+        assert_eq!(amount_2, Decimal::new(112, 2));
+        assert_eq!(serialized_2, "1,-1,10,0,false\n");
+        assert_eq!(amount_8, Decimal::new(112345679, 8));
+        assert_eq!(serialized_8, "1,1.12345679,10,0,false\n");
+        assert_eq!(serialized_0, "1,10,10,0,false\n");
+    }
+
+    #[test]
+    fn test_signed_amounts() {
+        let mut reader = csv::ReaderBuilder::new().from_reader(
+            "type,client,tx,amount\n\
+             deposit,1,1,+10.50\n\
+             deposit,1,2,-10.50\n\
+             deposit,1,3,+-10.50\n"
+                .as_bytes(),
+        );
+        let mut transactions = reader.deserialize::<Transaction>();
+
+        let plus = transactions.next().unwrap().unwrap();
+        assert_eq!(plus.amount, Some(Decimal::new(105000, 4)));
+        let minus = transactions.next().unwrap().unwrap();
+        assert_eq!(minus.amount, Some(Decimal::new(-105000, 4)));
+        assert!(transactions.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_amount_exceeding_precision() {
+        let mut reader = csv::ReaderBuilder::new().from_reader(
+            "type,client,tx,amount\n\
+             deposit,1,1,1234567890123456789012345678901234567890\n\
+             deposit,1,2,1.5\n"
+                .as_bytes(),
+        );
+        let mut transactions = reader.deserialize::<Transaction>();
+
+        let err = transactions.next().unwrap().unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "amount '1234567890123456789012345678901234567890' exceeds supported precision"
+            ),
+            "unexpected error: {err}"
+        );
+        // the row is skipped and reading continues with the next one
+        let transaction = transactions.next().unwrap().unwrap();
+        assert_eq!(transaction.amount, Some(Decimal::new(15, 1)));
+    }
+}
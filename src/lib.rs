@@ -0,0 +1,27 @@
+//! Reads deposits, withdrawals and disputes and computes the resulting client accounts. The
+//! binary is a thin CLI around this library, so the same processing can be embedded elsewhere:
+//!
+//! ```
+//! use account_transaction_reader::{AccountService, Transaction, TransactionType};
+//! use rust_decimal::Decimal;
+//!
+//! let mut service = AccountService::new();
+//! service
+//!     .record_transaction(Transaction {
+//!         r#type: TransactionType::Deposit,
+//!         client: 1,
+//!         tx: 1,
+//!         amount: Some(Decimal::from(10)),
+//!         timestamp: None,
+//!     })
+//!     .expect("a deposit to be applied");
+//! assert_eq!(service.summary()[&1].available, Decimal::from(10));
+//! ```
+
+pub mod data_structures;
+pub mod readers;
+pub mod services;
+pub mod writers;
+
+pub use data_structures::{Account, Transaction, TransactionType};
+pub use services::{AccountService, TransactionError};
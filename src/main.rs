@@ -1,84 +1,1283 @@
 use std::{
+    collections::HashSet,
+    fmt,
     fs::File,
-    io::{self, Write},
-    path::PathBuf,
+    io::{self, Read, Write},
+    iter,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
 
-use anyhow::{Context, Result};
-use clap::Parser;
+use anyhow::{Context, Result, bail};
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum, parser::ValueSource};
+use rust_decimal::Decimal;
 
-use crate::{data_structures::Transaction, services::AccountService};
-
-mod data_structures;
-mod services;
+use account_transaction_reader::{
+    data_structures::{
+        Account, DEFAULT_PRECISION, MAX_PRECISION, NegativeStyle, SummaryColumns, SummaryRow,
+        Transaction, format_amount, set_negative_style, set_precision,
+    },
+    readers::{
+        ByteRange, ByteRangeReader, CsvOptions, FieldWidths, FixedWidthReader, InputFormat,
+        IntervalReader, JsonArrayReader, JsonLinesReader, NullTokens, RowEvent, RowNumber,
+        ThousandsSeparator, TrimMode, TypeMap, last_rows, parse_interval, parse_quote,
+        read_csv_transactions, read_resolutions,
+    },
+    services::{
+        AccountService, AccountServiceOptions, DisputeValidator, DuplicateTxDetector, MinUnitMode,
+        OverflowMode, PARSE_ERROR_REASON, ProcessingStats, RejectionCounts, SummaryOrder,
+        TransactionError, TxRange, TxScope, WithdrawalDisputeModel, explain_account, run_benchmark,
+    },
+    writers::{
+        Balances, CsvSink, DeadLetterWriter, EventWriter, FinishWrite, JsonSink, LineTerminator,
+        OutputFormat, OutputTarget, SummarySink, TransactionPipe, Warning, WarningsFormat,
+        create_sink, is_broken_pipe, is_fifo, is_fifo_path, read_written_summary, summary_hash,
+        verify_round_trip, write_summary,
+    },
+};
 
 #[derive(Debug, Parser)]
 struct Args {
     #[arg(
-        help = "Path to the transactions .csv file",
+        help = "Paths to the transactions .csv files, read one after another into the same accounts. The transactions are read from the stdin if a path is `-` or none is given",
         index = 1,
-        required = true
+        num_args = 0..
     )]
-    pub transactions_file: PathBuf,
+    pub transactions_files: Vec<PathBuf>,
     #[arg(
-        help = "Whether to log errors to the stdout",
+        help = "Whether to log rows that couldn't be parsed or applied to the stdout",
         short = 'e',
         long = "log-errors",
         default_value = "false"
     )]
     pub log_errors: bool,
+    #[arg(
+        help = "Only log the first error to the stdout and the number of suppressed errors after it",
+        long = "first-error-only",
+        default_value = "false"
+    )]
+    pub first_error_only: bool,
+    #[arg(
+        help = "Report disputes, resolves and chargebacks that don't reference a prior transaction or dispute to the stderr",
+        long = "validate-disputes",
+        default_value = "false"
+    )]
+    pub validate_disputes: bool,
+    #[arg(
+        help = "Report transaction ids that are used by more than one deposit or withdrawal to the stderr",
+        long = "report-duplicate-tx",
+        default_value = "false"
+    )]
+    pub report_duplicate_tx: bool,
+    #[arg(
+        help = "Format of the transactions file",
+        long = "input-format",
+        value_enum,
+        default_value = "csv"
+    )]
+    pub input_format: InputFormat,
+    #[arg(
+        help = "Field layout of fixed-width input, e.g. `type:10,client:5,tx:10,amount:15`",
+        long = "field-widths",
+        required_if_eq("input_format", "fixed")
+    )]
+    pub field_widths: Option<FieldWidths>,
+    #[arg(
+        help = "Which csv fields get surrounding whitespace trimmed, quoted fields included",
+        long = "trim",
+        value_enum,
+        default_value = "all"
+    )]
+    pub trim: TrimMode,
+    #[arg(
+        help = "The csv file has no header, the columns are read in the order `type,client,tx,amount,timestamp`",
+        long = "no-header",
+        default_value = "false"
+    )]
+    pub no_header: bool,
+    #[arg(
+        help = "Comma separated tokens that mean there's no amount, e.g. `-,N/A`",
+        long = "null-tokens",
+        value_delimiter = ','
+    )]
+    pub null_tokens: Vec<String>,
+    #[arg(
+        help = "Strip this thousands grouping separator from the client, tx and amount fields, e.g. `,` to read `\"1,000\"` as 1000",
+        long = "thousands-separator"
+    )]
+    pub thousands_separator: Option<ThousandsSeparator>,
+    #[arg(
+        help = "Character that quotes csv fields containing the delimiter, e.g. `'`",
+        long = "quote",
+        default_value = "\"",
+        value_parser = parse_quote
+    )]
+    pub quote: u8,
+    #[arg(
+        help = "Exit with an error after writing the summary if any account is locked",
+        long = "fail-on-lock",
+        default_value = "false"
+    )]
+    pub fail_on_lock: bool,
+    #[arg(
+        help = "Exit with an error after writing the summary if any dispute is still open",
+        long = "require-dispute-closure",
+        default_value = "false"
+    )]
+    pub require_dispute_closure: bool,
+    #[arg(
+        help = "How negative amounts are written in the summary",
+        long = "negative-style",
+        value_enum,
+        default_value = "minus"
+    )]
+    pub negative_style: NegativeStyle,
+    #[arg(
+        help = "Number of fractional digits amounts are rounded to when they're read and written",
+        long = "precision",
+        default_value_t = DEFAULT_PRECISION,
+        value_parser = clap::value_parser!(u32).range(0..=MAX_PRECISION as i64)
+    )]
+    pub precision: u32,
+    #[arg(
+        help = "Write every applied transaction in canonical form to this .csv file",
+        long = "emit-applied"
+    )]
+    pub emit_applied: Option<PathBuf>,
+    #[arg(
+        help = "Run this shell command and write every applied transaction in canonical form to its stdin",
+        long = "pipe-to"
+    )]
+    pub pipe_to: Option<String>,
+    #[arg(
+        help = "Write every row that couldn't be parsed or applied along with the reason to this .csv file",
+        long = "dead-letter"
+    )]
+    pub dead_letter: Option<PathBuf>,
+    #[arg(
+        help = "Sum deposits of a client that share a transaction id into a single deposit",
+        long = "sum-duplicate-tx",
+        default_value = "false"
+    )]
+    pub sum_duplicate_tx: bool,
+    #[arg(
+        help = "Whether transaction ids are unique across all clients or every client has its own ids",
+        long = "tx-scope",
+        value_enum,
+        default_value = "global"
+    )]
+    pub tx_scope: TxScope,
+    #[arg(
+        help = "Whether a disputed withdrawal is held as a provisional refund or reversed right away",
+        long = "withdrawal-dispute-model",
+        value_enum,
+        default_value = "refund"
+    )]
+    pub withdrawal_dispute_model: WithdrawalDisputeModel,
+    #[arg(
+        help = "JSON file mapping custom transaction type tokens to the known types, e.g. `{\"1\": \"deposit\"}`",
+        long = "type-map"
+    )]
+    pub type_map: Option<PathBuf>,
+    #[arg(
+        help = "Only process the records starting within this `START:END` byte range of the file",
+        long = "byte-range"
+    )]
+    pub byte_range: Option<ByteRange>,
+    #[arg(
+        help = "Reject the transactions whose tx id is outside of this inclusive `MIN:MAX` range, e.g. the ids a partner declared to use",
+        long = "tx-range"
+    )]
+    pub tx_range: Option<TxRange>,
+    #[arg(
+        help = "Print a narrative of what happened to the account of this client to the stderr",
+        long = "explain-account"
+    )]
+    pub explain_account: Option<u16>,
+    #[arg(
+        help = "Write the summary to a file instead of the stdout, given as `PATH` or `FORMAT:PATH` with the format being `csv` or `json`. Without a format `.json` files are written as json and anything else as csv. Paths ending in `.gz` are gzip compressed. Can be repeated to write several files in one run",
+        short = 'o',
+        long = "output"
+    )]
+    pub outputs: Vec<OutputTarget>,
+    #[arg(
+        help = "Buffer all transactions and apply them ordered by the optional integer `timestamp` column",
+        long = "sort-by-timestamp",
+        default_value = "false"
+    )]
+    pub sort_by_timestamp: bool,
+    #[arg(
+        help = "Print the cumulative disputed amount of every client to the stderr",
+        long = "report-disputed-volume",
+        default_value = "false"
+    )]
+    pub report_disputed_volume: bool,
+    #[arg(
+        help = "Print a SHA-256 of the written summary rows, sorted by client and independent of the amount formatting, to the stderr",
+        long = "summary-hash",
+        default_value = "false"
+    )]
+    pub summary_hash: bool,
+    #[arg(
+        help = "Add the deposited and withdrawn totals of every client to the summary",
+        long = "wide",
+        default_value = "false"
+    )]
+    pub wide: bool,
+    #[arg(
+        help = "Add the semicolon separated ids of the transactions of every client that are under dispute to the summary",
+        long = "with-open-disputes",
+        default_value = "false"
+    )]
+    pub with_open_disputes: bool,
+    #[arg(
+        help = "Add the share of the total funds of every client that is held to the summary",
+        long = "with-frozen-ratio",
+        default_value = "false"
+    )]
+    pub with_frozen_ratio: bool,
+    #[arg(
+        help = "Create missing parent directories of the output files",
+        long = "create-dirs",
+        default_value = "false"
+    )]
+    pub create_dirs: bool,
+    #[arg(
+        help = "Line ending of the csv summary",
+        long = "line-terminator",
+        value_enum,
+        default_value = "lf"
+    )]
+    pub line_terminator: LineTerminator,
+    #[arg(
+        help = "Format of the summary written to the stdout, the files of --output have their own",
+        long = "output-format",
+        value_enum,
+        default_value = "csv"
+    )]
+    pub output_format: OutputFormat,
+    #[arg(
+        help = "Abort without writing the summary once more than this many rows couldn't be parsed or applied",
+        long = "tolerate-n-errors"
+    )]
+    pub tolerate_n_errors: Option<usize>,
+    #[arg(
+        help = "Keep reading transactions from the stdin until it closes and write a snapshot of the summary to the outputs every given number of seconds",
+        long = "stream-interval",
+        value_parser = parse_interval,
+        conflicts_with = "sort_by_timestamp"
+    )]
+    pub stream_interval: Option<Duration>,
+    #[arg(
+        help = "Record this many synthetic transactions instead of reading a file and print the throughput",
+        long = "benchmark"
+    )]
+    pub benchmark: Option<usize>,
+    #[arg(
+        help = "Report locked accounts that still hold funds of open disputes to the stderr",
+        long = "verify-lock-consistency",
+        default_value = "false"
+    )]
+    pub verify_lock_consistency: bool,
+    #[arg(
+        help = "Check after every applied transaction that the account's available funds don't exceed its total and report violations to the stderr",
+        long = "debug-invariants",
+        default_value = "false"
+    )]
+    pub debug_invariants: bool,
+    #[arg(
+        help = "Only process the first N data rows",
+        long = "head",
+        conflicts_with = "tail"
+    )]
+    pub head: Option<usize>,
+    #[arg(
+        help = "Only process the last N data rows, which are buffered until the input ends",
+        long = "tail"
+    )]
+    pub tail: Option<usize>,
+    #[arg(
+        help = "Spill disputable transactions beyond `--spill-threshold` to a temporary file in this directory to bound the memory use",
+        long = "spill-dir"
+    )]
+    pub spill_dir: Option<PathBuf>,
+    #[arg(
+        help = "Number of disputable transactions kept in memory when `--spill-dir` is set",
+        long = "spill-threshold",
+        default_value = "1000000",
+        requires = "spill_dir"
+    )]
+    pub spill_threshold: usize,
+    #[arg(
+        help = "Read the written summary back after writing it and fail if it doesn't hold exactly the accounts in the written order, columns and line endings",
+        long = "round-trip-check",
+        default_value = "false",
+        conflicts_with_all = ["finalize_on_chargeback", "stream_interval"]
+    )]
+    pub round_trip_check: bool,
+    #[arg(
+        help = "How parse errors and skipped transactions are reported",
+        long = "warnings-format",
+        value_enum,
+        default_value = "human"
+    )]
+    pub warnings_format: WarningsFormat,
+    #[arg(
+        help = "Reject disputes, resolves and chargebacks whose amount differs from the disputed transaction and report them to the stderr",
+        long = "verify-dispute-amounts",
+        default_value = "false"
+    )]
+    pub verify_dispute_amounts: bool,
+    #[arg(
+        help = "Reject disputes, resolves and chargebacks that have any amount and report them to the stderr",
+        long = "reject-dispute-amounts",
+        default_value = "false",
+        conflicts_with = "verify_dispute_amounts"
+    )]
+    pub reject_dispute_amounts: bool,
+    #[arg(
+        help = "Reject and report disputes of further transactions once this many transactions were disputed",
+        long = "max-tracked-disputes"
+    )]
+    pub max_tracked_disputes: Option<usize>,
+    #[arg(
+        help = "Forget the transactions of an account once a chargeback locks it, since they can't be disputed anymore",
+        long = "prune-locked",
+        default_value = "false"
+    )]
+    pub prune_locked: bool,
+    #[arg(
+        help = "Keep only this many of the latest deposits and withdrawals disputable to bound the memory, older ones can't be disputed anymore",
+        long = "max-disputable"
+    )]
+    pub max_disputable: Option<usize>,
+    #[arg(
+        help = "Smallest unit of the currency, e.g. `0.01`. Deposits and withdrawals with finer amounts are handled according to --min-unit-mode",
+        long = "min-unit"
+    )]
+    pub min_unit: Option<Decimal>,
+    #[arg(
+        help = "Whether amounts that aren't a multiple of --min-unit are rejected or rounded to the nearest multiple",
+        long = "min-unit-mode",
+        value_enum,
+        default_value = "reject",
+        requires = "min_unit"
+    )]
+    pub min_unit_mode: MinUnitMode,
+    #[arg(
+        help = "Whether a transaction that would overflow a balance is skipped, clamps the balance to the largest representable amount or aborts the processing",
+        long = "on-overflow",
+        value_enum,
+        default_value = "skip"
+    )]
+    pub on_overflow: OverflowMode,
+    #[arg(
+        help = "Resolve the open disputes of the transaction ids in the `tx` column of this .csv file after the transactions file",
+        long = "resolutions"
+    )]
+    pub resolutions: Option<PathBuf>,
+    #[arg(
+        help = "Write accounts to the summary as soon as a chargeback locks them, and the rest once the input ends. An account that is unlocked again is written once more with the rest",
+        long = "finalize-on-chargeback",
+        default_value = "false",
+        conflicts_with = "stream_interval"
+    )]
+    pub finalize_on_chargeback: bool,
+    #[arg(
+        help = "Only write the accounts of clients that had at least one dispute to the summary",
+        long = "disputed-clients-only",
+        default_value = "false"
+    )]
+    pub disputed_clients_only: bool,
+    #[arg(
+        help = "Leave accounts with a total below this balance out of the summary",
+        long = "min-balance"
+    )]
+    pub min_balance: Option<Decimal>,
+    #[arg(
+        help = "Print the number of accounts per total balance range to the stderr, split at these comma separated bounds, e.g. `0,100,1000`",
+        long = "balance-histogram",
+        value_delimiter = ',',
+        allow_hyphen_values = true
+    )]
+    pub balance_histogram: Vec<Decimal>,
+    #[arg(
+        help = "Keep locked accounts in the summary even if their total is below --min-balance",
+        long = "always-show-locked",
+        default_value = "false",
+        requires = "min_balance"
+    )]
+    pub always_show_locked: bool,
+    #[arg(
+        help = "Log how many rows were rejected for each reason to the stderr",
+        long = "explain-rejections",
+        default_value = "false"
+    )]
+    pub explain_rejections: bool,
+    #[arg(
+        help = "Order of the accounts in the summary",
+        long = "sort",
+        value_enum,
+        default_value = "client"
+    )]
+    pub sort: SummaryOrder,
+    #[arg(
+        help = "Log how many rows were read, applied by type, unparsable or skipped to the stderr",
+        long = "stats",
+        default_value = "false"
+    )]
+    pub stats: bool,
+    #[arg(
+        help = "Write how every applied transaction changed the balances of its account to this file, as a JSON object per line",
+        long = "events"
+    )]
+    pub events: Option<PathBuf>,
+    #[arg(
+        help = "Refuse to process transaction files larger than this many bytes, the stdin isn't limited",
+        long = "max-file-bytes"
+    )]
+    pub max_file_bytes: Option<u64>,
+    #[arg(
+        help = "Exit with an error after writing the summary if the available and held funds of any account don't add up to its total",
+        long = "verify",
+        default_value = "false"
+    )]
+    pub verify: bool,
+}
+
+fn summary_columns(args: &Args) -> SummaryColumns {
+    SummaryColumns {
+        wide: args.wide,
+        open_disputes: args.with_open_disputes,
+        frozen_ratio: args.with_frozen_ratio,
+    }
+}
+
+/// Whether the account of the client belongs in the summary according to the args.
+fn is_selected(args: &Args, account_service: &AccountService, client: u16) -> bool {
+    if args.disputed_clients_only && !account_service.had_dispute(client) {
+        return false;
+    }
+    if let Some(min_balance) = args.min_balance
+        && let Some(account) = account_service.account(client)
+        && account.total < min_balance
+    {
+        return args.always_show_locked && account.locked;
+    }
+    true
+}
+
+/// The rows of the summary with the columns and accounts selected by the args.
+fn summary_rows<'a>(
+    args: &'a Args,
+    account_service: &'a AccountService,
+) -> impl Iterator<Item = SummaryRow<'a>> {
+    account_service
+        .summary_rows(summary_columns(args), args.sort)
+        .filter(move |row| is_selected(args, account_service, row.client()))
+}
+
+/// The accounts of the summary in the order they're written.
+fn summary_accounts<'a>(
+    args: &'a Args,
+    account_service: &'a AccountService,
+) -> impl Iterator<Item = &'a Account> {
+    summary_rows(args, account_service).map(|row| {
+        account_service
+            .account(row.client())
+            .expect("the summarized account to exist")
+    })
+}
+
+/// Treats the input as corrupt once more rows than tolerated couldn't be parsed or applied.
+fn check_error_tolerance(tolerated: Option<usize>, error_count: usize) -> Result<()> {
+    if let Some(tolerated) = tolerated
+        && error_count > tolerated
+    {
+        bail!("aborting after {error_count} erroneous rows, only {tolerated} are tolerated");
+    }
+    Ok(())
+}
+
+/// Logs parse and record errors to the stdout, every one with `--log-errors`, only the first one
+/// with `--first-error-only` while counting the rest.
+struct ErrorLog {
+    enabled: bool,
+    first_only: bool,
+    logged: usize,
+    suppressed: usize,
+}
+
+impl ErrorLog {
+    fn new(args: &Args) -> Self {
+        Self {
+            enabled: args.log_errors || args.first_error_only,
+            first_only: args.first_error_only,
+            logged: 0,
+            suppressed: 0,
+        }
+    }
+
+    /// Returns whether the error was suppressed, so it isn't reported anywhere else either.
+    fn log(&mut self, writer: &mut impl Write, message: fmt::Arguments) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if self.first_only && self.logged > 0 {
+            self.suppressed += 1;
+            return true;
+        }
+        let _ = writeln!(writer, "{message}");
+        let _ = writer.flush();
+        self.logged += 1;
+        false
+    }
+
+    fn write_suppressed_count(&self, writer: &mut impl Write) {
+        if self.suppressed > 0 {
+            let _ = writeln!(writer, "suppressed {} further error(s)", self.suppressed);
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// Sink of the summary on the stdout, which is where it goes without any `--output`.
+fn stdout_sink<'a>(
+    args: &Args,
+    writer: impl FinishWrite + 'a,
+    flush_each_row: bool,
+) -> Box<dyn SummarySink + 'a> {
+    match args.output_format {
+        OutputFormat::Csv => Box::new(CsvSink::new(writer, flush_each_row, args.line_terminator)),
+        OutputFormat::Json => Box::new(JsonSink::new(writer, flush_each_row)),
+    }
+}
+
+/// Writes the current state of the summary, replacing the previous snapshot in the output files.
+/// Snapshots written to the stdout are appended one after another.
+fn write_snapshot(
+    args: &Args,
+    account_service: &AccountService,
+    stdout_writer: &mut impl Write,
+) -> Result<()> {
+    let sinks = if args.outputs.is_empty() {
+        vec![stdout_sink(args, stdout_writer, true)]
+    } else {
+        args.outputs
+            .iter()
+            .map(|target| create_sink(target, args.create_dirs, args.line_terminator))
+            .collect::<Result<Vec<_>>>()?
+    };
+    write_summary(sinks, summary_rows(args, account_service))
+}
+
+/// Reading options by argument id and flag, along with the input formats that use them.
+const READING_ARGS: [(&str, &str, &[InputFormat]); 5] = [
+    ("trim", "--trim", &[InputFormat::Csv]),
+    ("no_header", "--no-header", &[InputFormat::Csv]),
+    ("quote", "--quote", &[InputFormat::Csv]),
+    (
+        "null_tokens",
+        "--null-tokens",
+        &[InputFormat::Csv, InputFormat::Fixed],
+    ),
+    (
+        "thousands_separator",
+        "--thousands-separator",
+        &[InputFormat::Csv, InputFormat::Fixed],
+    ),
+];
+
+/// Open one of the transaction files, `-` being the stdin, and parse it in the input format.
+fn read_transactions(
+    args: &Args,
+    transactions_path: &Path,
+) -> Result<Box<dyn Iterator<Item = Result<Transaction>> + Send>> {
+    let transactions_file: Box<dyn Read + Send> = if transactions_path == Path::new("-") {
+        Box::new(io::stdin())
+    } else {
+        let transactions_file = File::open(transactions_path).with_context(|| {
+            format!(
+                "failed to open transactions file '{}'",
+                transactions_path.display()
+            )
+        })?;
+        match args.byte_range {
+            Some(byte_range) => Box::new(
+                ByteRangeReader::new(
+                    transactions_file,
+                    byte_range,
+                    args.input_format == InputFormat::Csv && !args.no_header,
+                )
+                .context("failed to seek to the byte range")?,
+            ),
+            None => Box::new(transactions_file),
+        }
+    };
+
+    Ok(match args.input_format {
+        InputFormat::Csv => read_csv_transactions(
+            transactions_file,
+            CsvOptions {
+                trim: args.trim,
+                has_headers: !args.no_header,
+                null_tokens: NullTokens::new(args.null_tokens.clone()),
+                thousands_separator: args.thousands_separator,
+                quote: args.quote,
+            },
+        ),
+        InputFormat::Fixed => Box::new(
+            FixedWidthReader::new(
+                transactions_file,
+                args.field_widths
+                    .clone()
+                    .expect("field widths to be required for fixed-width input"),
+            )
+            .with_null_tokens(NullTokens::new(args.null_tokens.clone()))
+            .with_thousands_separator(args.thousands_separator),
+        ),
+        InputFormat::Jsonl => Box::new(JsonLinesReader::new(transactions_file)),
+        InputFormat::JsonArray => Box::new(JsonArrayReader::new(transactions_file)),
+    })
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
 
     let stdout = io::stdout();
+    let flush_each_row = is_fifo(&stdout);
     let mut stdout_writer = io::BufWriter::new(stdout);
 
-    if !args.transactions_file.exists() {
-        panic!(
-            "transaction file '{}' doesn't exist",
-            args.transactions_file.display()
+    if let Some(rows) = args.benchmark {
+        let report = run_benchmark(rows);
+        writeln!(
+            stdout_writer,
+            "recorded {} transactions in {:.3}s ({:.0} rows/sec)",
+            report.rows,
+            report.elapsed.as_secs_f64(),
+            report.rows_per_sec()
+        )?;
+        if let Some(peak_memory_kib) = report.peak_memory_kib {
+            writeln!(stdout_writer, "peak memory: {peak_memory_kib} KiB")?;
+        }
+        stdout_writer.flush()?;
+        return Ok(());
+    }
+
+    let transactions_paths = if args.transactions_files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        args.transactions_files.clone()
+    };
+    for transactions_path in &transactions_paths {
+        let read_stdin = transactions_path == Path::new("-");
+        if !read_stdin && !transactions_path.exists() {
+            panic!(
+                "transaction file '{}' doesn't exist",
+                transactions_path.display()
+            );
+        }
+        if !read_stdin && !transactions_path.is_file() {
+            panic!("'{}' is not a file", transactions_path.display());
+        }
+        if let Some(max_file_bytes) = args.max_file_bytes
+            && !read_stdin
+        {
+            let file_bytes = transactions_path
+                .metadata()
+                .with_context(|| {
+                    format!(
+                        "failed to read the size of '{}'",
+                        transactions_path.display()
+                    )
+                })?
+                .len();
+            if file_bytes > max_file_bytes {
+                bail!(
+                    "transaction file '{}' has {file_bytes} bytes, more than the --max-file-bytes limit of {max_file_bytes}",
+                    transactions_path.display()
+                );
+            }
+        }
+        if read_stdin && args.byte_range.is_some() {
+            bail!(
+                "--byte-range needs a seekable transactions file, it can't be used with the stdin"
+            );
+        }
+        if !read_stdin && args.stream_interval.is_some() {
+            bail!("--stream-interval reads the transactions from the stdin, pass `-` or no file");
+        }
+    }
+    // the readers of other formats would silently ignore these
+    if let Some((_, flag, _)) = READING_ARGS.iter().find(|(id, _, formats)| {
+        !formats.contains(&args.input_format)
+            && matches.value_source(id) == Some(ValueSource::CommandLine)
+    }) {
+        bail!(
+            "{flag} can't be used with --input-format {}",
+            args.input_format
+                .to_possible_value()
+                .expect("no input format to be skipped")
+                .get_name()
         );
     }
-    if !args.transactions_file.is_file() {
-        panic!("'{}' is not a file", args.transactions_file.display());
+    if transactions_paths.len() > 1 && args.byte_range.is_some() {
+        bail!("--byte-range can only be used with a single transactions file");
+    }
+    if args.round_trip_check
+        && let Some(target) = args
+            .outputs
+            .iter()
+            .find(|target| is_fifo_path(&target.path))
+    {
+        bail!(
+            "--round-trip-check can't read the summary back from the fifo '{}'",
+            target.path.display()
+        );
     }
 
-    let mut account_service = AccountService::new();
+    if args
+        .min_unit
+        .is_some_and(|min_unit| min_unit <= Decimal::ZERO)
+    {
+        bail!("--min-unit must be positive");
+    }
 
-    let transactions_file =
-        File::open(args.transactions_file).context("failed to open transactions file")?;
+    set_negative_style(args.negative_style);
+    set_precision(args.precision);
 
-    let mut transactions_reader = csv::ReaderBuilder::new()
-        .trim(csv::Trim::All)
-        .from_reader(transactions_file);
+    let mut account_service = AccountService::with_options(AccountServiceOptions {
+        sum_duplicate_tx: args.sum_duplicate_tx,
+        tx_scope: args.tx_scope,
+        withdrawal_dispute_model: args.withdrawal_dispute_model,
+        verify_dispute_amounts: args.verify_dispute_amounts,
+        reject_dispute_amounts: args.reject_dispute_amounts,
+        max_tracked_disputes: args.max_tracked_disputes,
+        prune_locked_accounts: args.prune_locked,
+        max_disputable_transactions: args.max_disputable,
+        min_unit: args.min_unit,
+        min_unit_mode: args.min_unit_mode,
+        overflow_mode: args.on_overflow,
+        tx_range: args.tx_range,
+    });
+    if let Some(spill_dir) = &args.spill_dir {
+        account_service
+            .spill_to(spill_dir, args.spill_threshold)
+            .with_context(|| format!("failed to create spill file in '{}'", spill_dir.display()))?;
+    }
+    let type_map = args
+        .type_map
+        .as_deref()
+        .map(TypeMap::from_path)
+        .transpose()?;
+    let mut dispute_validator = args.validate_disputes.then(DisputeValidator::new);
+    let mut duplicate_tx_detector = args.report_duplicate_tx.then(DuplicateTxDetector::new);
+    let mut rejection_counts = args.explain_rejections.then(RejectionCounts::new);
+    let mut stats = args.stats.then(ProcessingStats::new);
+    let mut sinks = args
+        .outputs
+        .iter()
+        .map(|target| create_sink(target, args.create_dirs, args.line_terminator))
+        .collect::<Result<Vec<_>>>()?;
+    if sinks.is_empty() && args.finalize_on_chargeback {
+        // finalized accounts are written while the stdout writer is still used to log errors
+        sinks.push(stdout_sink(
+            &args,
+            io::BufWriter::new(io::stdout()),
+            flush_each_row,
+        ));
+    }
+    let mut finalized_clients = HashSet::new();
+    let mut applied_writer = args
+        .emit_applied
+        .as_ref()
+        .map(csv::Writer::from_path)
+        .transpose()
+        .context("failed to create applied transactions file")?;
+    let mut dead_letter_writer = args
+        .dead_letter
+        .as_deref()
+        .map(DeadLetterWriter::create)
+        .transpose()?;
+    let mut event_writer = args
+        .events
+        .as_deref()
+        .map(EventWriter::create)
+        .transpose()?;
+    let mut transaction_pipe = args
+        .pipe_to
+        .as_deref()
+        .map(TransactionPipe::spawn)
+        .transpose()?;
 
-    for (idx, transaction_res) in transactions_reader.deserialize::<Transaction>().enumerate() {
+    let name_files = transactions_paths.len() > 1;
+    let mut rows: Box<dyn Iterator<Item = (RowNumber, Result<Transaction>)> + Send> =
+        Box::new(iter::empty());
+    for transactions_path in &transactions_paths {
+        let file: Option<Arc<Path>> = name_files.then(|| Arc::from(transactions_path.as_path()));
         // we add 1 to the index because the first line is the header
-        let row_number = idx + 1;
+        let file_rows = read_transactions(&args, transactions_path)?
+            .enumerate()
+            .map(move |(idx, transaction_res)| {
+                let row_number = RowNumber {
+                    file: file.clone(),
+                    row: idx + 1,
+                };
+                (row_number, transaction_res)
+            });
+        rows = Box::new(rows.chain(file_rows));
+    }
+    let rows: Box<dyn Iterator<Item = (RowNumber, Result<Transaction>)> + Send> =
+        match (args.head, args.tail) {
+            (Some(head), _) => Box::new(rows.take(head)),
+            (_, Some(tail)) => Box::new(last_rows(rows, tail).into_iter()),
+            _ => Box::new(rows),
+        };
+    let rows: Box<dyn Iterator<Item = (RowNumber, Result<Transaction>)> + Send> =
+        if args.sort_by_timestamp {
+            let mut rows = rows.collect::<Vec<_>>();
+            // the sort is stable so transactions with the same timestamp keep their file order. rows
+            // without a timestamp, including the ones that failed to parse, come first
+            rows.sort_by_key(|(_, transaction_res)| {
+                transaction_res
+                    .as_ref()
+                    .ok()
+                    .and_then(|transaction| transaction.timestamp)
+            });
+            Box::new(rows.into_iter())
+        } else {
+            Box::new(rows)
+        };
+
+    let events: Box<dyn Iterator<Item = RowEvent>> = match args.stream_interval {
+        Some(interval) => Box::new(IntervalReader::spawn(rows, interval)),
+        None => Box::new(
+            rows.map(|(row_number, transaction_res)| RowEvent::Row(row_number, transaction_res)),
+        ),
+    };
 
-        let transaction = match transaction_res {
+    let mut error_log = ErrorLog::new(&args);
+    let mut error_count = 0;
+    for event in events {
+        let (row_number, transaction_res) = match event {
+            RowEvent::Row(row_number, transaction_res) => (row_number, transaction_res),
+            RowEvent::Tick => {
+                let write_res = write_snapshot(&args, &account_service, &mut stdout_writer);
+                match write_res {
+                    Err(err) if is_broken_pipe(&err) => return Ok(()),
+                    res => res?,
+                }
+                continue;
+            }
+        };
+        let mut transaction = match transaction_res {
             Ok(v) => v,
             Err(err) => {
-                if args.log_errors {
-                    let _ = writeln!(stdout_writer, "error parsing row {row_number}: {err:?}");
-                    let _ = stdout_writer.flush();
+                if let Some(counts) = rejection_counts.as_mut() {
+                    counts.observe_parse_error();
+                }
+                if let Some(stats) = stats.as_mut() {
+                    stats.observe_parse_error();
+                }
+                if let Some(writer) = dead_letter_writer.as_mut() {
+                    writer.write_parse_error(&row_number, &err)?;
                 }
+                if args.warnings_format == WarningsFormat::Json {
+                    Warning {
+                        row: row_number.row,
+                        file: row_number.file_name(),
+                        tx: None,
+                        client: None,
+                        reason: PARSE_ERROR_REASON,
+                        detail: format!("{err:#}"),
+                    }
+                    .write_json_line(&mut io::stderr().lock())?;
+                } else {
+                    error_log.log(
+                        &mut stdout_writer,
+                        format_args!("error parsing {row_number}: {err:#}"),
+                    );
+                }
+                error_count += 1;
+                check_error_tolerance(args.tolerate_n_errors, error_count)?;
                 continue;
             }
         };
 
-        account_service.record_transaction(transaction);
+        if let Some(type_map) = &type_map {
+            type_map.apply(&mut transaction);
+        }
+
+        if let Some(validator) = dispute_validator.as_mut() {
+            validator.observe(&row_number, &transaction);
+        }
+        if let Some(detector) = duplicate_tx_detector.as_mut() {
+            detector.observe(&row_number, &transaction);
+        }
+
+        let recorded_transaction = (applied_writer.is_some()
+            || transaction_pipe.is_some()
+            || dead_letter_writer.is_some()
+            || event_writer.is_some())
+        .then(|| transaction.clone());
+        let (client, tx) = (transaction.client, transaction.tx);
+        let balances_before = event_writer.is_some().then(|| {
+            account_service
+                .account(client)
+                .map(Balances::of)
+                .unwrap_or_default()
+        });
+        let r#type = stats.is_some().then(|| transaction.r#type.clone());
+        let record_res = account_service.record_transaction(transaction);
+        if args.on_overflow == OverflowMode::Abort
+            && record_res == Err(TransactionError::BalanceOverflow)
+        {
+            bail!("{row_number}: tx {tx} overflows the balances of client {client}");
+        }
+        // a broken spill file isn't a problem of the row, the following ones would fail as well
+        if let Err(err @ TransactionError::SpillFailed(_)) = &record_res {
+            bail!("{row_number}: {err}");
+        }
+        if let (Some(stats), Some(r#type)) = (stats.as_mut(), &r#type) {
+            stats.observe(r#type, &record_res);
+        }
+        let mut is_suppressed = false;
+        if let Err(err) = &record_res {
+            if args.warnings_format != WarningsFormat::Json {
+                is_suppressed = error_log.log(
+                    &mut stdout_writer,
+                    format_args!("error recording {row_number}: tx {tx}, {err}"),
+                );
+            }
+            error_count += 1;
+            check_error_tolerance(args.tolerate_n_errors, error_count)?;
+        }
+        if let (Some(counts), Err(err)) = (rejection_counts.as_mut(), &record_res) {
+            counts.observe(err);
+        }
+        if let (Some(writer), Some(transaction), Err(err)) = (
+            dead_letter_writer.as_mut(),
+            recorded_transaction.as_ref(),
+            &record_res,
+        ) {
+            writer.write_rejected(&row_number, transaction, err)?;
+        }
+        match record_res {
+            Ok(()) => {
+                if args.debug_invariants
+                    && let Some(account) = account_service.invariant_violation(client)
+                {
+                    writeln!(
+                        io::stderr(),
+                        "{row_number}: tx {tx} left client {client} with available {} above total {}",
+                        format_amount(&account.available),
+                        format_amount(&account.total)
+                    )?;
+                }
+                if let Some(applied_transaction) = &recorded_transaction {
+                    if let Some(writer) = applied_writer.as_mut() {
+                        writer.serialize(applied_transaction)?;
+                    }
+                    if let Some(pipe) = transaction_pipe.as_mut() {
+                        pipe.forward(applied_transaction)?;
+                    }
+                    if let (Some(writer), Some(before), Some(account)) = (
+                        event_writer.as_mut(),
+                        balances_before,
+                        account_service.account(client),
+                    ) {
+                        writer.write(applied_transaction, before, Balances::of(account))?;
+                    }
+                }
+
+                let is_locked = account_service
+                    .account(client)
+                    .is_some_and(|account| account.locked);
+                if args.finalize_on_chargeback
+                    && is_locked
+                    && is_selected(&args, &account_service, client)
+                    && finalized_clients.insert(client)
+                {
+                    let row = account_service
+                        .summary_row(client, summary_columns(&args))
+                        .expect("the locked account to exist");
+                    let write_res = sinks
+                        .iter_mut()
+                        .try_for_each(|sink| sink.write_row(&row).and_then(|()| sink.flush()));
+                    match write_res {
+                        Err(err) if is_broken_pipe(&err) => return Ok(()),
+                        res => res?,
+                    }
+                } else if !is_locked {
+                    // an unlock reopened the account, so its finalized row is outdated
+                    finalized_clients.remove(&client);
+                }
+            }
+            Err(err) if args.warnings_format == WarningsFormat::Json => {
+                Warning {
+                    row: row_number.row,
+                    file: row_number.file_name(),
+                    tx: Some(tx),
+                    client: Some(client),
+                    reason: err.reason(),
+                    detail: err.to_string(),
+                }
+                .write_json_line(&mut io::stderr().lock())?;
+            }
+            Err(
+                err @ (TransactionError::DisputeAmountMismatch { .. }
+                | TransactionError::UnexpectedAmount(_)
+                | TransactionError::DisputeLimitReached { .. }
+                | TransactionError::SubUnitAmount { .. }
+                | TransactionError::BalanceOverflow
+                | TransactionError::TxOutOfRange(_)
+                | TransactionError::UnknownType(_)),
+            ) if !is_suppressed => {
+                writeln!(io::stderr(), "{row_number}: rejected tx {tx}, {err}")?;
+            }
+            Err(_) => {}
+        }
+    }
+
+    if let Some(path) = &args.resolutions {
+        for tx in read_resolutions(path)? {
+            if let Err(err) = account_service.resolve_tx(tx) {
+                writeln!(io::stderr(), "resolution of tx {tx} not applied, {err}")?;
+            }
+        }
+    }
+
+    error_log.write_suppressed_count(&mut stdout_writer);
+
+    if let Some(mut writer) = applied_writer {
+        writer.flush().context("flush applied transactions")?;
+    }
+
+    if let Some(mut writer) = dead_letter_writer {
+        writer.flush()?;
+    }
+
+    if let Some(mut writer) = event_writer {
+        writer.flush()?;
+    }
+
+    if let Some(pipe) = transaction_pipe {
+        let status = pipe.finish()?;
+        if status.closed_early {
+            writeln!(
+                io::stderr(),
+                "the pipe command stopped reading before all applied transactions were forwarded"
+            )?;
+        }
+        if !status.exit_status.success() {
+            writeln!(
+                io::stderr(),
+                "the pipe command exited with {}",
+                status.exit_status
+            )?;
+        }
     }
 
-    let mut csv_writer = csv::WriterBuilder::new()
-        .has_headers(true)
-        .from_writer(stdout_writer);
-    for (_, account) in account_service.summary() {
-        csv_writer.serialize(account)?;
+    if let Some(validator) = dispute_validator {
+        let mut stderr = io::stderr().lock();
+        writeln!(
+            stderr,
+            "dispute validation found {} violation(s)",
+            validator.violations.len()
+        )?;
+        for violation in &validator.violations {
+            writeln!(stderr, "{violation}")?;
+        }
+    }
+
+    if let Some(detector) = duplicate_tx_detector {
+        let duplicates = detector.duplicates();
+        let mut stderr = io::stderr().lock();
+        writeln!(
+            stderr,
+            "found {} duplicate transaction id(s)",
+            duplicates.len()
+        )?;
+        for duplicate in &duplicates {
+            writeln!(stderr, "{duplicate}")?;
+        }
+    }
+
+    for clamped_resolve in &account_service.clamped_resolves {
+        writeln!(io::stderr(), "{clamped_resolve}")?;
+    }
+    for clamped_chargeback in &account_service.clamped_chargebacks {
+        writeln!(io::stderr(), "{clamped_chargeback}")?;
+    }
+    for rounded_amount in &account_service.rounded_amounts {
+        writeln!(io::stderr(), "{rounded_amount}")?;
+    }
+
+    if let Some(counts) = rejection_counts {
+        write!(io::stderr(), "{} row(s) rejected\n{counts}", counts.total())?;
+    }
+
+    if let Some(stats) = stats {
+        write!(io::stderr(), "{stats}")?;
+    }
+
+    if !args.balance_histogram.is_empty() {
+        let histogram = account_service.balance_histogram(args.balance_histogram.clone());
+        write!(io::stderr(), "accounts by total balance\n{histogram}")?;
+    }
+
+    if args.verify_lock_consistency {
+        let stuck_accounts = account_service.stuck_locked_accounts();
+        let mut stderr = io::stderr().lock();
+        writeln!(
+            stderr,
+            "found {} locked account(s) with held funds",
+            stuck_accounts.len()
+        )?;
+        for account in stuck_accounts {
+            writeln!(
+                stderr,
+                "client {} is locked with {} held by disputes that can't be settled anymore",
+                account.client,
+                format_amount(&account.held)
+            )?;
+        }
+    }
+
+    if args.report_disputed_volume {
+        let mut clients = account_service
+            .summary()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        clients.sort();
+        let mut stderr = io::stderr().lock();
+        for client in clients {
+            writeln!(
+                stderr,
+                "client {client} disputed volume: {}",
+                format_amount(&account_service.disputed_volume(client))
+            )?;
+        }
+    }
+
+    if let Some(client) = args.explain_account {
+        writeln!(
+            io::stderr(),
+            "{}",
+            explain_account(&account_service, client)
+        )?;
+    }
+
+    if args.summary_hash {
+        writeln!(
+            io::stderr(),
+            "summary sha256: {}",
+            summary_hash(summary_accounts(&args, &account_service))
+        )?;
+    }
+
+    let write_res = if args.stream_interval.is_some() {
+        // the snapshots already replaced the files the sinks were opened on
+        drop(sinks);
+        write_snapshot(&args, &account_service, &mut stdout_writer)
+    } else if sinks.is_empty() && args.round_trip_check {
+        // the stdout can't be read back, so the summary is checked before it's passed on
+        let mut written = Vec::new();
+        let write_res = write_summary(
+            vec![stdout_sink(&args, &mut written, false)],
+            summary_rows(&args, &account_service),
+        )
+        .and_then(|()| {
+            verify_round_trip(
+                summary_accounts(&args, &account_service),
+                &written,
+                args.output_format,
+                args.line_terminator,
+            )
+        });
+        write_res.and_then(|()| {
+            stdout_writer.write_all(&written)?;
+            stdout_writer.flush()?;
+            Ok(())
+        })
+    } else {
+        if sinks.is_empty() {
+            sinks.push(stdout_sink(&args, stdout_writer, flush_each_row));
+        }
+        write_summary(
+            sinks,
+            summary_rows(&args, &account_service)
+                .filter(|row| !finalized_clients.contains(&row.client())),
+        )
+    };
+    match write_res {
+        // whoever reads our output went away so there's nobody left to write the summary to
+        Err(err) if is_broken_pipe(&err) => return Ok(()),
+        res => res?,
+    }
+
+    if args.round_trip_check {
+        for target in &args.outputs {
+            verify_round_trip(
+                summary_accounts(&args, &account_service),
+                &read_written_summary(&target.path)?,
+                target.format,
+                args.line_terminator,
+            )
+            .with_context(|| format!("summary '{}'", target.path.display()))?;
+        }
+    }
+
+    if args.fail_on_lock {
+        let locked_accounts = account_service
+            .summary()
+            .values()
+            .filter(|account| account.locked)
+            .count();
+        if locked_accounts > 0 {
+            bail!("{locked_accounts} account(s) got locked by a chargeback");
+        }
+    }
+
+    if args.verify
+        && let Err(clients) = account_service.verify_invariants()
+    {
+        let mut stderr = io::stderr().lock();
+        for client in &clients {
+            writeln!(stderr, "balances of client {client} don't add up")?;
+        }
+        bail!(
+            "{} account(s) have available and held funds that don't add up to the total",
+            clients.len()
+        );
+    }
+
+    if args.require_dispute_closure {
+        let mut open_disputes = account_service
+            .open_dispute_txs()
+            .into_iter()
+            .collect::<Vec<_>>();
+        if !open_disputes.is_empty() {
+            open_disputes.sort();
+            let mut stderr = io::stderr().lock();
+            for (client, txs) in &open_disputes {
+                let txs = txs.iter().map(ToString::to_string).collect::<Vec<_>>();
+                writeln!(
+                    stderr,
+                    "client {client} has open disputes of tx {}",
+                    txs.join(", ")
+                )?;
+            }
+            let count = open_disputes
+                .iter()
+                .map(|(_, txs)| txs.len())
+                .sum::<usize>();
+            bail!("{count} dispute(s) were neither resolved nor charged back");
+        }
     }
-    csv_writer.flush().context("flush account summary as csv")?;
 
     Ok(())
 }
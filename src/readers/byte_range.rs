@@ -0,0 +1,145 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    str::FromStr,
+};
+
+/// Half-open byte range `START:END` of the input. A record belongs to the range in which its
+/// first byte lies, so complementary ranges like `0:100` and `100:200` process every record
+/// exactly once. Records are assumed to be single lines, quoted fields containing line breaks
+/// aren't supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl FromStr for ByteRange {
+    type Err = String;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let (start, end) = range
+            .split_once(':')
+            .ok_or_else(|| format!("expected `START:END` but got '{range}'"))?;
+        let start = start
+            .parse::<u64>()
+            .map_err(|err| format!("invalid range start: {err}"))?;
+        let end = end
+            .parse::<u64>()
+            .map_err(|err| format!("invalid range end: {err}"))?;
+        if start > end {
+            return Err(format!("range start {start} is after its end {end}"));
+        }
+
+        Ok(Self { start, end })
+    }
+}
+
+/// Reads the header line, if there is one, followed by the records that start within the range.
+pub struct ByteRangeReader<R: Read> {
+    reader: BufReader<R>,
+    /// Offset in the underlying reader of the next line to read
+    position: u64,
+    end: u64,
+    line: Vec<u8>,
+    consumed: usize,
+}
+
+impl<R: Read + Seek> ByteRangeReader<R> {
+    pub fn new(reader: R, range: ByteRange, has_header: bool) -> io::Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let mut header = Vec::new();
+        if has_header {
+            reader.read_until(b'\n', &mut header)?;
+        }
+
+        let mut position = header.len() as u64;
+        if range.start > position {
+            // the range starts within a record that belongs to the previous range unless the byte
+            // right before the start is the end of a line
+            reader.seek(SeekFrom::Start(range.start - 1))?;
+            let mut partial_line = Vec::new();
+            position = range.start - 1 + reader.read_until(b'\n', &mut partial_line)? as u64;
+        }
+
+        Ok(Self {
+            reader,
+            position,
+            end: range.end,
+            line: header,
+            consumed: 0,
+        })
+    }
+}
+
+impl<R: Read> Read for ByteRangeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.consumed == self.line.len() {
+            if self.position >= self.end {
+                return Ok(0);
+            }
+
+            self.line.clear();
+            self.consumed = 0;
+            let read = self.reader.read_until(b'\n', &mut self.line)?;
+            if read == 0 {
+                return Ok(0);
+            }
+            self.position += read as u64;
+        }
+
+        let remaining = &self.line[self.consumed..];
+        let len = remaining.len().min(buf.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.consumed += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn read_range(input: &str, range: &str) -> String {
+        let mut reader =
+            ByteRangeReader::new(Cursor::new(input), range.parse().unwrap(), true).unwrap();
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_ranges_align_to_records() {
+        let input = "type,client,tx,amount\ndeposit,1,1,1\ndeposit,2,2,2\ndeposit,3,3,3\n";
+        // the header is 22 bytes long and every record 14 bytes
+        assert_eq!(read_range(input, "0:22"), "type,client,tx,amount\n");
+        assert_eq!(
+            read_range(input, "0:23"),
+            "type,client,tx,amount\ndeposit,1,1,1\n"
+        );
+        assert_eq!(read_range(input, "23:36"), "type,client,tx,amount\n");
+        assert_eq!(
+            read_range(input, "23:37"),
+            "type,client,tx,amount\ndeposit,2,2,2\n"
+        );
+        assert_eq!(
+            read_range(input, "36:1000"),
+            "type,client,tx,amount\ndeposit,2,2,2\ndeposit,3,3,3\n"
+        );
+        assert_eq!(
+            read_range(input, "37:1000"),
+            "type,client,tx,amount\ndeposit,3,3,3\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(
+            "10:20".parse::<ByteRange>(),
+            Ok(ByteRange { start: 10, end: 20 })
+        );
+        assert!("20:10".parse::<ByteRange>().is_err());
+        assert!("10".parse::<ByteRange>().is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    io::{BufRead, BufReader, Lines, Read},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+use csv::StringRecord;
+
+use crate::{
+    data_structures::Transaction,
+    readers::{NullTokens, ThousandsSeparator, grouped_columns},
+};
+
+/// Column layout of a fixed-width file, e.g. `type:10,client:5,tx:10,amount:15`. The field names
+/// must match the csv header names so the sliced fields can be deserialized the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldWidths {
+    fields: Vec<(String, usize)>,
+}
+
+impl FromStr for FieldWidths {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut fields = Vec::new();
+        for field in spec.split(',') {
+            let (name, width) = field
+                .split_once(':')
+                .ok_or_else(|| format!("expected `name:width` but got '{field}'"))?;
+            let width = width
+                .trim()
+                .parse::<usize>()
+                .map_err(|err| format!("invalid width for field '{name}': {err}"))?;
+            if width == 0 {
+                return Err(format!("width of field '{name}' must be greater than zero"));
+            }
+            fields.push((name.trim().to_string(), width));
+        }
+
+        Ok(Self { fields })
+    }
+}
+
+impl FieldWidths {
+    fn headers(&self) -> StringRecord {
+        self.fields.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Slice a line into its fields. Lines that are shorter than the full layout yield empty
+    /// values for the missing fields, which is how trailing optional fields like `amount` are
+    /// usually represented.
+    fn slice(&self, line: &str) -> StringRecord {
+        let mut chars = line.chars();
+        self.fields
+            .iter()
+            .map(|(_, width)| {
+                let field: String = chars.by_ref().take(*width).collect();
+                field.trim().to_string()
+            })
+            .collect()
+    }
+}
+
+pub struct FixedWidthReader<R: Read> {
+    lines: Lines<BufReader<R>>,
+    field_widths: FieldWidths,
+    headers: StringRecord,
+    null_tokens: NullTokens,
+    thousands_separator: Option<ThousandsSeparator>,
+}
+
+impl<R: Read> FixedWidthReader<R> {
+    pub fn new(reader: R, field_widths: FieldWidths) -> Self {
+        Self {
+            lines: BufReader::new(reader).lines(),
+            headers: field_widths.headers(),
+            field_widths,
+            null_tokens: NullTokens::default(),
+            thousands_separator: None,
+        }
+    }
+
+    pub fn with_null_tokens(mut self, null_tokens: NullTokens) -> Self {
+        self.null_tokens = null_tokens;
+        self
+    }
+
+    pub fn with_thousands_separator(
+        mut self,
+        thousands_separator: Option<ThousandsSeparator>,
+    ) -> Self {
+        self.thousands_separator = thousands_separator;
+        self
+    }
+}
+
+impl<R: Read> Iterator for FixedWidthReader<R> {
+    type Item = Result<Transaction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err).context("failed to read fixed-width line")),
+            };
+            // blank lines carry no record, typically a trailing newline at the end of the file
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let amount_column = self.headers.iter().position(|name| name == "amount");
+            let mut record = self
+                .null_tokens
+                .blank(self.field_widths.slice(&line), amount_column);
+            if let Some(separator) = self.thousands_separator {
+                record = separator.strip(record, &grouped_columns(&self.headers));
+            }
+            return Some(
+                record
+                    .deserialize(Some(&self.headers))
+                    .with_context(|| format!("failed to parse fixed-width record '{line}'")),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::data_structures::TransactionType;
+
+    #[test]
+    fn test_parse_field_widths() {
+        let field_widths = FieldWidths::from_str("type:10,client:5,tx:10,amount:15").unwrap();
+        assert_eq!(
+            field_widths.fields,
+            vec![
+                ("type".to_string(), 10),
+                ("client".to_string(), 5),
+                ("tx".to_string(), 10),
+                ("amount".to_string(), 15),
+            ]
+        );
+
+        assert!(FieldWidths::from_str("type:10,client").is_err());
+        assert!(FieldWidths::from_str("type:0").is_err());
+    }
+
+    #[test]
+    fn test_read_fixed_width_records() {
+        let field_widths = FieldWidths::from_str("type:10,client:5,tx:10,amount:15").unwrap();
+        let input = "deposit   1    1         1.5\ndispute   1    1\n\n";
+
+        let transactions = FixedWidthReader::new(input.as_bytes(), field_widths)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].r#type, TransactionType::Deposit);
+        assert_eq!(transactions[0].client, 1);
+        assert_eq!(transactions[0].tx, 1);
+        assert_eq!(transactions[0].amount, Some(Decimal::new(15, 1)));
+        assert_eq!(transactions[1].r#type, TransactionType::Dispute);
+        assert_eq!(transactions[1].amount, None);
+    }
+}
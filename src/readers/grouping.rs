@@ -0,0 +1,79 @@
+use std::str::FromStr;
+
+use csv::StringRecord;
+
+/// Columns whose values may be written with thousands grouping, e.g. `1,000`.
+pub const GROUPED_COLUMNS: [&str; 3] = ["client", "tx", "amount"];
+
+/// Separator that partners use to group the thousands of numbers. It's stripped from the
+/// [`GROUPED_COLUMNS`] before they're parsed, so `1,000` reads as `1000`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThousandsSeparator(char);
+
+impl FromStr for ThousandsSeparator {
+    type Err = String;
+
+    fn from_str(separator: &str) -> Result<Self, Self::Err> {
+        let mut chars = separator.chars();
+        let (Some(separator), None) = (chars.next(), chars.next()) else {
+            return Err(format!("expected a single character but got '{separator}'"));
+        };
+        // amounts use `.` for their fraction so it can't group the thousands at the same time
+        if separator == '.' || separator == '-' || separator.is_ascii_digit() {
+            return Err(format!("'{separator}' can't be used to group thousands"));
+        }
+
+        Ok(Self(separator))
+    }
+}
+
+impl ThousandsSeparator {
+    /// Strip the separator from the fields in the given columns.
+    pub fn strip(&self, record: StringRecord, columns: &[usize]) -> StringRecord {
+        let is_grouped = columns.iter().any(|column| {
+            record
+                .get(*column)
+                .is_some_and(|field| field.contains(self.0))
+        });
+        if !is_grouped {
+            return record;
+        }
+
+        let mut stripped = record
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                if columns.contains(&idx) {
+                    field.replace(self.0, "")
+                } else {
+                    field.to_string()
+                }
+            })
+            .collect::<StringRecord>();
+        stripped.set_position(record.position().cloned());
+        stripped
+    }
+}
+
+/// Positions of the [`GROUPED_COLUMNS`] among the given column names.
+pub fn grouped_columns<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    names
+        .into_iter()
+        .enumerate()
+        .filter(|(_, name)| GROUPED_COLUMNS.contains(name))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_thousands_separator() {
+        assert_eq!("_".parse(), Ok(ThousandsSeparator('_')));
+        assert!(".".parse::<ThousandsSeparator>().is_err());
+        assert!("1".parse::<ThousandsSeparator>().is_err());
+        assert!(",,".parse::<ThousandsSeparator>().is_err());
+    }
+}
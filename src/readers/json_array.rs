@@ -0,0 +1,140 @@
+use std::io::{BufRead, BufReader, Read};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::data_structures::Transaction;
+
+/// Reads a single JSON array of transaction objects, one element at a time so the whole array is
+/// never held in memory. An element that isn't a valid transaction is an error of its own, but
+/// malformed JSON ends the reading since there's no telling where the next element starts.
+pub struct JsonArrayReader<R: Read> {
+    reader: BufReader<R>,
+    started: bool,
+    done: bool,
+}
+
+impl<R: Read> JsonArrayReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            started: false,
+            done: false,
+        }
+    }
+
+    /// The next byte that isn't whitespace, without consuming it.
+    fn peek(&mut self) -> Result<Option<u8>> {
+        loop {
+            let buffer = self
+                .reader
+                .fill_buf()
+                .context("failed to read json array")?;
+            match buffer.first() {
+                Some(byte) if byte.is_ascii_whitespace() => self.reader.consume(1),
+                byte => return Ok(byte.copied()),
+            }
+        }
+    }
+
+    /// Move past the separator to the start of the next element, `false` at the end of the array.
+    fn advance_to_element(&mut self) -> Result<bool> {
+        let separator = self.peek()?;
+        match (self.started, separator) {
+            (false, Some(b'[')) => {
+                self.started = true;
+                self.reader.consume(1);
+                if self.peek()? == Some(b']') {
+                    self.reader.consume(1);
+                    return Ok(false);
+                }
+                Ok(true)
+            }
+            (false, _) => bail!("expected a json array of transactions"),
+            (true, Some(separator @ (b',' | b']'))) => {
+                self.reader.consume(1);
+                Ok(separator == b',')
+            }
+            (true, Some(byte)) => bail!(
+                "expected `,` or `]` after a transaction but got '{}'",
+                byte as char
+            ),
+            (true, None) => bail!("unexpected end of the json array"),
+        }
+    }
+
+    fn next_transaction(&mut self) -> Result<Option<Transaction>> {
+        if !self.advance_to_element()? {
+            return Ok(None);
+        }
+        let mut deserializer = serde_json::Deserializer::from_reader(&mut self.reader);
+        let value = serde_json::Value::deserialize(&mut deserializer)
+            .context("failed to read json array")?;
+        // the element was read in full, so the following ones can still be read
+        self.done = false;
+        Transaction::deserialize(&value)
+            .with_context(|| format!("failed to parse json transaction '{value}'"))
+            .map(Some)
+    }
+}
+
+impl<R: Read> Iterator for JsonArrayReader<R> {
+    type Item = Result<Transaction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // anything but an invalid transaction leaves the reader at an unknown position
+        self.done = true;
+        self.next_transaction().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::data_structures::TransactionType;
+
+    #[test]
+    fn test_read_json_array() {
+        let input = r#"[
+            {"type": "deposit", "client": 1, "tx": 1, "amount": "1.5"},
+            {"type": "deposit", "client": 1},
+            {"type": "dispute", "client": 1, "tx": 1}
+        ]"#;
+
+        let transactions = JsonArrayReader::new(input.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(transactions.len(), 3);
+        let deposit = transactions[0].as_ref().unwrap();
+        assert_eq!(deposit.r#type, TransactionType::Deposit);
+        assert_eq!(deposit.amount, Some(Decimal::new(15, 1)));
+        assert!(transactions[1].is_err());
+        assert_eq!(
+            transactions[2].as_ref().unwrap().r#type,
+            TransactionType::Dispute
+        );
+
+        assert_eq!(JsonArrayReader::new(" [ ] ".as_bytes()).count(), 0);
+    }
+
+    #[test]
+    fn test_malformed_json_ends_reading() {
+        let input = r#"[{"type": "deposit", "client": 1, "tx": 1, "amount": "1"} {"type"#;
+
+        let transactions = JsonArrayReader::new(input.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(transactions.len(), 2);
+        assert!(transactions[0].is_ok());
+        assert!(transactions[1].is_err());
+        assert!(
+            JsonArrayReader::new("{}".as_bytes())
+                .next()
+                .unwrap()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use std::io::{BufRead, BufReader, Lines, Read};
+
+use anyhow::{Context, Result};
+
+use crate::data_structures::Transaction;
+
+/// Reads one JSON object per line, e.g. `{"type":"deposit","client":1,"tx":1,"amount":"1.5"}`.
+/// Amounts are strings like in the csv input, as JSON numbers would lose precision.
+pub struct JsonLinesReader<R: Read> {
+    lines: Lines<BufReader<R>>,
+}
+
+impl<R: Read> JsonLinesReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: BufReader::new(reader).lines(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for JsonLinesReader<R> {
+    type Item = Result<Transaction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err).context("failed to read json line")),
+            };
+            // blank lines carry no record, typically a trailing newline at the end of the file
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(
+                serde_json::from_str(&line)
+                    .with_context(|| format!("failed to parse json line '{line}'")),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::data_structures::TransactionType;
+
+    #[test]
+    fn test_read_json_lines() {
+        let input = "{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":\"1.5\"}\n\
+                     {\"type\":\"dispute\",\"client\":1,\"tx\":1}\n\
+                     \n\
+                     {\"type\":\"deposit\",\"client\":1,\"tx\":2,\"amount\":1.5}\n";
+
+        let transactions = JsonLinesReader::new(input.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(transactions.len(), 3);
+        let deposit = transactions[0].as_ref().unwrap();
+        assert_eq!(deposit.r#type, TransactionType::Deposit);
+        assert_eq!(deposit.client, 1);
+        assert_eq!(deposit.tx, 1);
+        assert_eq!(deposit.amount, Some(Decimal::new(15, 1)));
+        let dispute = transactions[1].as_ref().unwrap();
+        assert_eq!(dispute.r#type, TransactionType::Dispute);
+        assert_eq!(dispute.amount, None);
+        // numeric amounts are rejected instead of silently rounded through a float
+        assert!(transactions[2].is_err());
+    }
+}
@@ -0,0 +1,310 @@
+mod byte_range;
+mod fixed_width;
+mod grouping;
+mod json_array;
+mod json_lines;
+mod null_tokens;
+mod resolutions;
+mod row_number;
+mod sample;
+mod stream;
+mod type_map;
+pub use byte_range::*;
+pub use fixed_width::*;
+pub use grouping::*;
+pub use json_array::*;
+pub use json_lines::*;
+pub use null_tokens::*;
+pub use resolutions::*;
+pub use row_number::*;
+pub use sample::*;
+pub use stream::*;
+pub use type_map::*;
+
+use std::io::Read;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use csv::StringRecord;
+
+use crate::data_structures::Transaction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    Csv,
+    /// Fixed-width records, sliced according to `--field-widths`
+    Fixed,
+    /// One JSON object per line with the csv columns as keys. Amounts must be strings like
+    /// `"1.5"`, JSON numbers are rejected as they'd be rounded through a float
+    Jsonl,
+    /// A single JSON array of objects with the csv columns as keys. Amounts must be strings like
+    /// `"1.5"`, JSON numbers are rejected as they'd be rounded through a float
+    JsonArray,
+}
+
+/// Which csv fields get leading and trailing whitespace trimmed. Trimming happens after unquoting
+/// so quotes don't protect whitespace, e.g. `" 10.5 "` is read as `10.5` unless the fields are
+/// left untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TrimMode {
+    /// Trim neither the header nor the records
+    None,
+    /// Only trim the header
+    Headers,
+    /// Only trim the records
+    Fields,
+    All,
+}
+
+impl From<TrimMode> for csv::Trim {
+    fn from(mode: TrimMode) -> Self {
+        match mode {
+            TrimMode::None => csv::Trim::None,
+            TrimMode::Headers => csv::Trim::Headers,
+            TrimMode::Fields => csv::Trim::Fields,
+            TrimMode::All => csv::Trim::All,
+        }
+    }
+}
+
+/// Columns of header-less csv input, in the order they're read.
+pub const POSITIONAL_COLUMNS: [&str; 5] = ["type", "client", "tx", "amount", "timestamp"];
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub trim: TrimMode,
+    /// Without a header the fields are mapped by their position, see [`POSITIONAL_COLUMNS`]
+    pub has_headers: bool,
+    pub null_tokens: NullTokens,
+    pub thousands_separator: Option<ThousandsSeparator>,
+    /// Character that wraps fields containing the delimiter, see [`parse_quote`]
+    pub quote: u8,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            trim: TrimMode::All,
+            has_headers: true,
+            null_tokens: NullTokens::default(),
+            thousands_separator: None,
+            quote: b'"',
+        }
+    }
+}
+
+/// Parse the quote character of the csv input, which the csv reader only supports as a single
+/// ascii character.
+pub fn parse_quote(quote: &str) -> Result<u8, String> {
+    match quote.as_bytes() {
+        [quote] if quote.is_ascii() && *quote != b',' => Ok(*quote),
+        _ => Err(format!(
+            "expected a single ascii character other than `,` but got '{quote}'"
+        )),
+    }
+}
+
+pub fn read_csv_transactions<R: Read + Send + 'static>(
+    reader: R,
+    options: CsvOptions,
+) -> Box<dyn Iterator<Item = Result<Transaction>> + Send> {
+    let CsvOptions {
+        trim,
+        has_headers,
+        null_tokens,
+        thousands_separator,
+        quote,
+    } = options;
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(trim.into())
+        .quote(quote)
+        .has_headers(has_headers)
+        .flexible(!has_headers)
+        .from_reader(reader);
+    let headers = if has_headers {
+        match reader.headers() {
+            Ok(headers) => Some(headers.clone()),
+            Err(err) => return Box::new(std::iter::once(Err(err.into()))),
+        }
+    } else {
+        None
+    };
+    let columns = match &headers {
+        Some(headers) => headers.iter().collect::<Vec<_>>(),
+        None => POSITIONAL_COLUMNS.to_vec(),
+    };
+    let amount_column = columns.iter().position(|name| *name == "amount");
+    let grouped_columns = grouped_columns(columns);
+
+    Box::new(reader.into_records().map(move |record_res| {
+        let mut record = null_tokens.blank(record_res?, amount_column);
+        if let Some(separator) = thousands_separator {
+            record = separator.strip(record, &grouped_columns);
+        }
+        record
+            .deserialize::<Transaction>(headers.as_ref())
+            .map_err(
+                |err| match headers.is_none().then(|| misplaced_column(&record)) {
+                    Some(Some(hint)) => anyhow::Error::from(err).context(hint),
+                    _ => err.into(),
+                },
+            )
+    }))
+}
+
+/// Explain which positional field obviously doesn't fit its column, which usually means that the
+/// file does have a header or its columns are ordered differently.
+fn misplaced_column(record: &StringRecord) -> Option<String> {
+    let client = record.get(1).unwrap_or_default();
+    let tx = record.get(2).unwrap_or_default();
+    let (column, value) = if client.parse::<u16>().is_err() {
+        ("client", client)
+    } else if tx.parse::<u32>().is_err() {
+        ("tx", tx)
+    } else {
+        return None;
+    };
+
+    Some(format!(
+        "'{value}' isn't a valid {column} but the columns are read as `{}` without a header. \
+         Does the file have a header or a different column order?",
+        POSITIONAL_COLUMNS.join(",")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    #[test]
+    fn test_trim_applies_inside_quotes() {
+        let input = "type,client,tx,amount\ndeposit,1,1,\" 10.5 \"\n";
+
+        let transactions = read_csv_transactions(input.as_bytes(), CsvOptions::default())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(transactions[0].amount, Some(Decimal::new(105000, 4)));
+
+        let mut transactions = read_csv_transactions(
+            input.as_bytes(),
+            CsvOptions {
+                trim: TrimMode::None,
+                ..Default::default()
+            },
+        );
+        assert!(transactions.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_read_without_header() {
+        let transactions = read_csv_transactions(
+            "deposit,1,1,10\ndispute,1,1\n".as_bytes(),
+            CsvOptions {
+                has_headers: false,
+                ..Default::default()
+            },
+        )
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].amount, Some(Decimal::from(10)));
+        assert_eq!(transactions[1].amount, None);
+    }
+
+    #[test]
+    fn test_misordered_data_without_header() {
+        let mut transactions = read_csv_transactions(
+            "1,deposit,1,10\n".as_bytes(),
+            CsvOptions {
+                has_headers: false,
+                ..Default::default()
+            },
+        );
+        let err = transactions.next().unwrap().unwrap_err();
+        assert!(
+            err.to_string().starts_with(
+                "'deposit' isn't a valid client but the columns are read as `type,client,tx,amount,timestamp` without a header"
+            ),
+            "unexpected error: {err:#}"
+        );
+
+        let mut transactions = read_csv_transactions(
+            "type,client,tx,amount\n".as_bytes(),
+            CsvOptions {
+                has_headers: false,
+                ..Default::default()
+            },
+        );
+        let err = transactions.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("Does the file have a header"));
+    }
+
+    #[test]
+    fn test_null_tokens() {
+        let input = "type,client,tx,amount\ndispute,1,1,N/A\ndispute,1,2,-\ndispute,1,3,\n";
+        let null_tokens = NullTokens::new(vec!["N/A".to_string(), "-".to_string()]);
+
+        let transactions = read_csv_transactions(
+            input.as_bytes(),
+            CsvOptions {
+                null_tokens,
+                ..Default::default()
+            },
+        )
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+        assert_eq!(transactions.len(), 3);
+        assert!(
+            transactions
+                .iter()
+                .all(|transaction| transaction.amount.is_none())
+        );
+
+        let mut transactions = read_csv_transactions(input.as_bytes(), CsvOptions::default());
+        assert!(transactions.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_thousands_separator() {
+        let input = "type,client,tx,amount\ndeposit,1,\"1,000\",\"1,234.5\"\n";
+
+        let transactions = read_csv_transactions(
+            input.as_bytes(),
+            CsvOptions {
+                thousands_separator: Some(",".parse().unwrap()),
+                ..Default::default()
+            },
+        )
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+        assert_eq!(transactions[0].tx, 1000);
+        assert_eq!(transactions[0].amount, Some(Decimal::new(12345, 1)));
+
+        let mut transactions = read_csv_transactions(input.as_bytes(), CsvOptions::default());
+        assert!(transactions.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_quote() {
+        let input = "type,client,tx,amount\ndeposit,1,1,'1,234.5'\ndeposit,1,2,'10'\n";
+
+        let transactions = read_csv_transactions(
+            input.as_bytes(),
+            CsvOptions {
+                thousands_separator: Some(",".parse().unwrap()),
+                quote: parse_quote("'").unwrap(),
+                ..Default::default()
+            },
+        )
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+        assert_eq!(transactions[0].amount, Some(Decimal::new(12345, 1)));
+        assert_eq!(transactions[1].amount, Some(Decimal::from(10)));
+
+        assert!(parse_quote("''").is_err());
+        assert!(parse_quote(",").is_err());
+        assert!(parse_quote("´").is_err());
+    }
+}
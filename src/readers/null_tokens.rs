@@ -0,0 +1,33 @@
+use csv::StringRecord;
+
+/// Tokens that partners use in the amount column to say that there's no amount, e.g. `-` or
+/// `N/A`. Matching fields are blanked before deserializing, which reads them as no amount.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NullTokens(Vec<String>);
+
+impl NullTokens {
+    pub fn new(tokens: Vec<String>) -> Self {
+        Self(tokens)
+    }
+
+    /// Blank the field in the given column if it's one of the tokens.
+    pub fn blank(&self, record: StringRecord, column: Option<usize>) -> StringRecord {
+        let Some(column) = column else {
+            return record;
+        };
+        let is_null = record
+            .get(column)
+            .is_some_and(|field| self.0.iter().any(|token| token == field));
+        if !is_null {
+            return record;
+        }
+
+        let mut blanked = record
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| if idx == column { "" } else { field })
+            .collect::<StringRecord>();
+        blanked.set_position(record.position().cloned());
+        blanked
+    }
+}
@@ -0,0 +1,26 @@
+use std::{fs::File, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct Resolution {
+    tx: u32,
+}
+
+/// Read the transaction ids of a csv file with a `tx` column, e.g. a batch of disputes that were
+/// resolved out-of-band. Other columns are ignored.
+pub fn read_resolutions(path: &Path) -> Result<Vec<u32>> {
+    let file = File::open(path).context("failed to open resolutions file")?;
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(file)
+        .into_deserialize::<Resolution>()
+        .enumerate()
+        .map(|(index, resolution_res)| {
+            resolution_res
+                .map(|resolution| resolution.tx)
+                .with_context(|| format!("failed to parse row {} of resolutions file", index + 1))
+        })
+        .collect()
+}
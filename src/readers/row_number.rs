@@ -0,0 +1,48 @@
+use std::{fmt, path::Path, sync::Arc};
+
+/// Position of a row in the input. The file is only named when several transaction files are
+/// read, row numbers start over in every file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowNumber {
+    pub file: Option<Arc<Path>>,
+    pub row: usize,
+}
+
+impl RowNumber {
+    pub fn new(row: usize) -> Self {
+        Self { file: None, row }
+    }
+
+    /// The file as it's shown in reports, `None` when only one transaction file is read.
+    pub fn file_name(&self) -> Option<String> {
+        self.file.as_ref().map(|file| file.display().to_string())
+    }
+}
+
+impl fmt::Display for RowNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}", self.row)?;
+        if let Some(file) = &self.file {
+            write!(f, " of '{}'", file.display())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(RowNumber::new(3).to_string(), "row 3");
+        assert_eq!(
+            RowNumber {
+                file: Some(Arc::from(Path::new("day2.csv"))),
+                row: 3,
+            }
+            .to_string(),
+            "row 3 of 'day2.csv'"
+        );
+    }
+}
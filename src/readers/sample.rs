@@ -0,0 +1,29 @@
+use std::collections::VecDeque;
+
+/// Keep only the last `count` items, buffering at most that many at a time.
+pub fn last_rows<I: Iterator>(rows: I, count: usize) -> VecDeque<I::Item> {
+    let mut buffer = VecDeque::with_capacity(count);
+    if count == 0 {
+        return buffer;
+    }
+
+    for row in rows {
+        if buffer.len() == count {
+            buffer.pop_front();
+        }
+        buffer.push_back(row);
+    }
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_rows() {
+        assert_eq!(last_rows(1..=5, 2), [4, 5]);
+        assert_eq!(last_rows(1..=2, 5), [1, 2]);
+        assert!(last_rows(1..=5, 0).is_empty());
+    }
+}
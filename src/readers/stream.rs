@@ -0,0 +1,127 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{
+    data_structures::{Transaction, precision, set_precision},
+    readers::RowNumber,
+};
+
+pub enum RowEvent {
+    Row(RowNumber, Result<Transaction>),
+    /// The interval elapsed, time to emit a snapshot of the summary
+    Tick,
+}
+
+/// Parse a positive number of seconds, fractions allowed, e.g. `0.5`.
+pub fn parse_interval(seconds: &str) -> Result<Duration, String> {
+    let seconds = seconds
+        .parse::<f64>()
+        .map_err(|err| format!("invalid number of seconds: {err}"))?;
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(format!("interval must be positive but got {seconds}"));
+    }
+
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// Reads the rows on a separate thread and interleaves them with a [`RowEvent::Tick`] every
+/// interval, including while the input is idle. Ends once the input is exhausted.
+pub struct IntervalReader {
+    receiver: Receiver<(RowNumber, Result<Transaction>)>,
+    interval: Duration,
+    next_tick: Instant,
+}
+
+impl IntervalReader {
+    pub fn spawn(
+        rows: impl Iterator<Item = (RowNumber, Result<Transaction>)> + Send + 'static,
+        interval: Duration,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        // the rows are parsed on the reading thread, which needs the amount precision of ours
+        let precision = precision();
+        thread::spawn(move || {
+            set_precision(precision);
+            for row in rows {
+                // the receiving side only goes away when processing stopped
+                if sender.send(row).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            receiver,
+            interval,
+            next_tick: Instant::now() + interval,
+        }
+    }
+}
+
+impl Iterator for IntervalReader {
+    type Item = RowEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = Instant::now();
+        if now >= self.next_tick {
+            self.next_tick = now + self.interval;
+            return Some(RowEvent::Tick);
+        }
+
+        match self.receiver.recv_timeout(self.next_tick - now) {
+            Ok((row_number, transaction_res)) => Some(RowEvent::Row(row_number, transaction_res)),
+            Err(RecvTimeoutError::Timeout) => {
+                self.next_tick = Instant::now() + self.interval;
+                Some(RowEvent::Tick)
+            }
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("1.5"), Ok(Duration::from_millis(1500)));
+        assert!(parse_interval("0").is_err());
+        assert!(parse_interval("-1").is_err());
+        assert!(parse_interval("soon").is_err());
+    }
+
+    #[test]
+    fn test_ticks_while_idle() {
+        let rows = (1..=2).map(|row_number| {
+            thread::sleep(Duration::from_millis(50));
+            (
+                RowNumber::new(row_number),
+                Err(anyhow::anyhow!("row {row_number}")),
+            )
+        });
+
+        let events = IntervalReader::spawn(rows, Duration::from_millis(20)).collect::<Vec<_>>();
+
+        let row_numbers = events
+            .iter()
+            .filter_map(|event| match event {
+                RowEvent::Row(row_number, _) => Some(row_number.row),
+                RowEvent::Tick => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(row_numbers, vec![1, 2]);
+        assert!(
+            events
+                .iter()
+                .filter(|event| matches!(event, RowEvent::Tick))
+                .count()
+                >= 2
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::data_structures::{Transaction, TransactionType};
+
+/// Maps partner specific transaction type tokens, e.g. `1` for deposits, to the known
+/// transaction types. Loaded from a JSON object like `{"1": "deposit", "2": "withdrawal"}`.
+#[derive(Debug, Deserialize)]
+pub struct TypeMap(HashMap<String, TransactionType>);
+
+impl TypeMap {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let file = File::open(path).context("failed to open type map")?;
+        let type_map: Self =
+            serde_json::from_reader(BufReader::new(file)).context("failed to parse type map")?;
+
+        for (token, r#type) in &type_map.0 {
+            if let TransactionType::Unknown(target) = r#type {
+                bail!("type map maps '{token}' to the unknown transaction type '{target}'");
+            }
+        }
+
+        Ok(type_map)
+    }
+
+    /// Translate the type of a transaction that wasn't recognized as one of the known types.
+    /// Tokens that aren't in the map stay `Unknown`.
+    pub fn apply(&self, transaction: &mut Transaction) {
+        if let TransactionType::Unknown(token) = &transaction.r#type
+            && let Some(r#type) = self.0.get(token)
+        {
+            transaction.r#type = r#type.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    #[test]
+    fn test_apply_type_map() {
+        let type_map: TypeMap = serde_json::from_str(r#"{"1": "deposit"}"#).unwrap();
+
+        let mut transaction = Transaction {
+            r#type: TransactionType::Unknown("1".to_string()),
+            client: 1,
+            tx: 1,
+            amount: Some(Decimal::from(10)),
+            timestamp: None,
+        };
+        type_map.apply(&mut transaction);
+        assert_eq!(transaction.r#type, TransactionType::Deposit);
+
+        transaction.r#type = TransactionType::Unknown("2".to_string());
+        type_map.apply(&mut transaction);
+        assert_eq!(
+            transaction.r#type,
+            TransactionType::Unknown("2".to_string())
+        );
+    }
+}
@@ -1,200 +1,1231 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fmt, io,
+    path::Path,
+};
 
+use clap::ValueEnum;
 use rust_decimal::Decimal;
 
-use crate::data_structures::{Account, Transaction, TransactionType};
+use crate::{
+    data_structures::{
+        Account, AccountActivity, DisputeState, SummaryColumns, SummaryRow, Transaction,
+        TransactionType, WideAccount, format_amount,
+    },
+    services::{BalanceHistogram, DisputableStore, ReversalError, TransactionError, TxRange},
+};
 
+/// How a dispute of a withdrawal moves funds. Disputes of deposits always hold the deposited funds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum WithdrawalDisputeModel {
+    /// The client claims the withdrawn funds back, so the disputed amount is credited to `held` as
+    /// a provisional refund. A resolve drops the refund again and a chargeback releases it to
+    /// `available`.
+    #[default]
+    Refund,
+    /// The withdrawal is reversed right away, crediting the amount back to `available`. A resolve
+    /// debits it again and a chargeback keeps the reversal.
+    Reversal,
+}
+
+/// Whose transactions a transaction id has to be unique among.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TxScope {
+    /// Ids are unique across all clients, a deposit or withdrawal reusing the id of another
+    /// client's transaction is rejected
+    #[default]
+    Global,
+    /// Every client has its own ids, so different clients may use the same id
+    Client,
+}
+
+/// What happens to deposits and withdrawals whose amount isn't a multiple of the minimum unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum MinUnitMode {
+    /// The transaction isn't applied
+    #[default]
+    Reject,
+    /// The amount is rounded to the nearest multiple of the minimum unit
+    Round,
+}
+
+/// Order of the accounts in the summary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SummaryOrder {
+    /// Ascending by client id
+    #[default]
+    Client,
+    /// The clients with the most applied disputes first, ties ascending by client id
+    DisputesDesc,
+}
+
+/// What happens when a transaction would take a balance beyond the representable range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OverflowMode {
+    /// The transaction isn't applied and processing continues
+    #[default]
+    Skip,
+    /// The overflowing balances are clamped to the largest or smallest representable amount
+    Saturate,
+    /// Processing stops with an error
+    Abort,
+}
+
+/// How a transaction moves the funds of an account.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct BalanceChange {
+    available: Decimal,
+    held: Decimal,
+    total: Decimal,
+}
+
+impl BalanceChange {
+    /// Both changes applied one after the other. The components of the changes a single
+    /// transaction combines never have the same sign, so this can't overflow.
+    fn then(self, other: Self) -> Self {
+        Self {
+            available: self.available + other.available,
+            held: self.held + other.held,
+            total: self.total + other.total,
+        }
+    }
+
+    /// The change that can actually be made to the account without overflowing any balance. Only
+    /// under [`OverflowMode::Saturate`] an overflowing balance is clamped instead of rejecting
+    /// the whole change.
+    fn checked(self, account: &Account, mode: OverflowMode) -> Result<Self, TransactionError> {
+        let fit = |balance: Decimal, change: Decimal| match balance.checked_add(change) {
+            Some(_) => Ok(change),
+            None if mode == OverflowMode::Saturate => Ok(balance.saturating_add(change) - balance),
+            None => Err(TransactionError::BalanceOverflow),
+        };
+        Ok(Self {
+            available: fit(account.available, self.available)?,
+            held: fit(account.held, self.held)?,
+            total: fit(account.total, self.total)?,
+        })
+    }
+
+    /// Add a change returned by [`BalanceChange::checked`] for this account.
+    fn add_to(self, account: &mut Account) {
+        account.available += self.available;
+        account.held += self.held;
+        account.total += self.total;
+    }
+
+    /// Apply the change to the account, all balances or none.
+    fn apply(self, account: &mut Account, mode: OverflowMode) -> Result<(), TransactionError> {
+        self.checked(account, mode)?.add_to(account);
+        Ok(())
+    }
+}
+
+/// Where the funds of a disputed transaction go, depending on what was disputed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DisputedFunds {
+    Deposit,
+    WithdrawalRefund,
+    WithdrawalReversal,
+}
+
+impl DisputedFunds {
+    fn dispute(self, amount: Decimal) -> BalanceChange {
+        match self {
+            Self::Deposit => BalanceChange {
+                available: -amount,
+                held: amount,
+                total: Decimal::ZERO,
+            },
+            Self::WithdrawalRefund => BalanceChange {
+                available: Decimal::ZERO,
+                held: amount,
+                total: amount,
+            },
+            Self::WithdrawalReversal => BalanceChange {
+                available: amount,
+                held: Decimal::ZERO,
+                total: amount,
+            },
+        }
+    }
+
+    /// A resolve undoes the dispute.
+    fn resolve(self, amount: Decimal) -> BalanceChange {
+        self.dispute(-amount)
+    }
+
+    fn chargeback(self, amount: Decimal) -> BalanceChange {
+        match self {
+            Self::Deposit => BalanceChange {
+                available: Decimal::ZERO,
+                held: -amount,
+                total: -amount,
+            },
+            Self::WithdrawalRefund => BalanceChange {
+                available: amount,
+                held: -amount,
+                total: Decimal::ZERO,
+            },
+            // the funds were already credited back by the dispute
+            Self::WithdrawalReversal => BalanceChange::default(),
+        }
+    }
+}
+
+/// A resolve that released less than the disputed amount because the account held less. This
+/// never happens unless the held funds went out of sync with the open disputes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClampedResolve {
+    pub client: u16,
+    pub tx: u32,
+    pub disputed: Decimal,
+    pub released: Decimal,
+}
+
+impl fmt::Display for ClampedResolve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "resolve of tx {} for client {} released {} instead of the disputed {}",
+            self.tx,
+            self.client,
+            format_amount(&self.released),
+            format_amount(&self.disputed)
+        )
+    }
+}
+
+/// A chargeback of a resolved deposit dispute that took back less than the disputed amount because
+/// the funds released by the resolve were partly spent already.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClampedChargeback {
+    pub client: u16,
+    pub tx: u32,
+    pub disputed: Decimal,
+    pub charged_back: Decimal,
+}
+
+impl fmt::Display for ClampedChargeback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "chargeback of tx {} for client {} took back {} instead of the disputed {}",
+            self.tx,
+            self.client,
+            format_amount(&self.charged_back),
+            format_amount(&self.disputed)
+        )
+    }
+}
+
+/// An amount that was rounded to a multiple of the minimum unit before it was applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundedAmount {
+    pub client: u16,
+    pub tx: u32,
+    pub amount: Decimal,
+    pub rounded: Decimal,
+}
+
+impl fmt::Display for RoundedAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "amount {} of tx {} for client {} was rounded to {}",
+            format_amount(&self.amount),
+            self.tx,
+            self.client,
+            format_amount(&self.rounded)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AccountServiceOptions {
+    /// Treat deposits that reuse the transaction id of an earlier deposit of the same client as
+    /// parts of a single split deposit. The amounts accumulate into the earlier transaction, so a
+    /// later dispute references the summed amount.
+    pub sum_duplicate_tx: bool,
+    pub tx_scope: TxScope,
+    pub withdrawal_dispute_model: WithdrawalDisputeModel,
+    /// Reject disputes, resolves and chargebacks that carry an amount which differs from the
+    /// amount of the disputed transaction. Without it, their amounts are ignored.
+    pub verify_dispute_amounts: bool,
+    /// Reject disputes, resolves and chargebacks that carry any amount at all, since the spec
+    /// has none for them. Takes precedence over `verify_dispute_amounts`.
+    pub reject_dispute_amounts: bool,
+    /// Reject disputes of transactions that were never disputed before once this many
+    /// transactions have a dispute state, bounding the memory a flood of disputes can take.
+    pub max_tracked_disputes: Option<usize>,
+    /// Forget the disputable transactions of an account once a chargeback locks it. A locked
+    /// account doesn't accept any transaction but an unlock, so they can only be disputed again if
+    /// the account gets unlocked.
+    pub prune_locked_accounts: bool,
+    /// Keep only this many of the latest deposits and withdrawals disputable, across all clients.
+    /// Disputes, resolves and chargebacks of older transactions are rejected as unknown, which
+    /// includes settling a dispute that was opened before the transaction got evicted.
+    pub max_disputable_transactions: Option<usize>,
+    /// Smallest unit of the currency, e.g. `0.01`. Deposits and withdrawals with an amount that
+    /// isn't a multiple of it are handled according to the `min_unit_mode`. Must be positive.
+    pub min_unit: Option<Decimal>,
+    pub min_unit_mode: MinUnitMode,
+    /// What to do when a transaction would overflow a balance. Only [`OverflowMode::Saturate`]
+    /// changes the outcome here, both other modes reject the transaction with
+    /// [`TransactionError::BalanceOverflow`] and it's up to the caller to abort.
+    pub overflow_mode: OverflowMode,
+    /// Reject transactions of any type whose id is outside of this range.
+    pub tx_range: Option<TxRange>,
+}
+
+/// The ledger of all client accounts. It's `Send`, so a server can share one between its request
+/// handlers behind a mutex. [`AccountService::record_transaction`] only takes the lock for a few
+/// map lookups and returns without holding on to any of the service's data, unless the looked up
+/// transaction was spilled to disk. [`AccountService::resolve_tx`] scans all disputes and holds the
+/// lock longer.
+///
+/// ```
+/// use std::{
+///     sync::{Arc, Mutex},
+///     thread,
+/// };
+///
+/// use account_transaction_reader::{AccountService, Transaction, TransactionType};
+/// use rust_decimal::Decimal;
+///
+/// let service = Arc::new(Mutex::new(AccountService::new()));
+/// let handlers: Vec<_> = (1..=2)
+///     .map(|client| {
+///         let service = Arc::clone(&service);
+///         thread::spawn(move || {
+///             let deposit = Transaction {
+///                 r#type: TransactionType::Deposit,
+///                 client,
+///                 tx: u32::from(client),
+///                 amount: Some(Decimal::from(10)),
+///                 timestamp: None,
+///             };
+///             // keep the lock only for the call itself, not for building the transaction
+///             service.lock().unwrap().record_transaction(deposit)
+///         })
+///     })
+///     .collect();
+/// for handler in handlers {
+///     handler.join().unwrap().expect("a deposit to be applied");
+/// }
+/// assert_eq!(service.lock().unwrap().summary().len(), 2);
+/// ```
 pub struct AccountService {
+    pub options: AccountServiceOptions,
     pub accounts: HashMap<u16, Account>,
-    /// Key: transaction id
-    pub disputable_transactions: HashMap<u32, Transaction>,
-    pub disputed_transaction_ids: HashSet<u32>,
-    pub resolved_dispute_ids: HashSet<u32>,
+    /// Key: (client id, transaction id). Scoping the transaction id by client means a dispute can
+    /// only ever reference a transaction of the client it's recorded for.
+    pub disputable_transactions: DisputableStore,
+    /// Key: transaction id, value: the client whose deposit or withdrawal used it. Only tracked
+    /// under [`TxScope::Global`], and unlike the disputable transactions never evicted.
+    pub tx_owners: HashMap<u32, u16>,
+    /// Key: (client id, transaction id). Transactions that were never disputed have no entry.
+    pub dispute_states: HashMap<(u16, u32), DisputeState>,
+    /// Key: (client id, transaction id). Deposits and withdrawals undone by
+    /// [`AccountService::apply_reversal`], they can't be disputed anymore.
+    pub reversed_transactions: HashSet<(u16, u32)>,
+    /// Resolves that couldn't release the full disputed amount, in the order they were applied.
+    pub clamped_resolves: Vec<ClampedResolve>,
+    /// Chargebacks of resolved disputes that couldn't take back the full disputed amount, in the
+    /// order they were applied.
+    pub clamped_chargebacks: Vec<ClampedChargeback>,
+    /// Amounts rounded to the minimum unit under [`MinUnitMode::Round`], in input order.
+    pub rounded_amounts: Vec<RoundedAmount>,
+    pub activity: HashMap<u16, AccountActivity>,
+}
+
+impl Default for AccountService {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AccountService {
     pub fn new() -> Self {
+        Self::with_options(AccountServiceOptions::default())
+    }
+
+    pub fn with_options(options: AccountServiceOptions) -> Self {
+        let mut disputable_transactions = DisputableStore::new();
+        if let Some(limit) = options.max_disputable_transactions {
+            disputable_transactions.limit_to(limit);
+        }
+
         Self {
+            options,
             accounts: HashMap::new(),
-            disputable_transactions: HashMap::new(),
-            disputed_transaction_ids: HashSet::new(),
-            resolved_dispute_ids: HashSet::new(),
-        }
-    }
-
-    /// Record a transaction for an account. This operates on good-will meaning that we don't
-    /// return an error if the transaction is invalid. Instead, we just don't perform any operations
-    /// on the account.
-    pub fn record_transaction(&mut self, transaction: Transaction) {
-        if !self.accounts.contains_key(&transaction.client) {
-            self.accounts.insert(
-                transaction.client,
-                Account {
-                    client: transaction.client,
-                    available: Decimal::ZERO,
-                    held: Decimal::ZERO,
-                    total: Decimal::ZERO,
-                    locked: false,
-                },
-            );
+            disputable_transactions,
+            tx_owners: HashMap::new(),
+            dispute_states: HashMap::new(),
+            reversed_transactions: HashSet::new(),
+            clamped_resolves: Vec::new(),
+            clamped_chargebacks: Vec::new(),
+            rounded_amounts: Vec::new(),
+            activity: HashMap::new(),
+        }
+    }
+
+    /// Record a transaction for an account. An invalid transaction doesn't change any account and
+    /// the error tells why it wasn't applied, it's up to the caller whether that's fatal. A dispute
+    /// of another client's transaction is an [`TransactionError::UnknownDisputeTarget`] since
+    /// transactions are only looked up among the client's own.
+    ///
+    /// Transactions of the same client must be recorded in the order they appear in the input,
+    /// since e.g. a dispute is only valid after the transaction it references and a withdrawal
+    /// depends on the deposits before it. Transactions of different clients never affect each
+    /// other, so any parallelization must only preserve the order within each client.
+    pub fn record_transaction(
+        &mut self,
+        mut transaction: Transaction,
+    ) -> Result<(), TransactionError> {
+        if let Some(range) = self.options.tx_range
+            && !range.contains(transaction.tx)
+        {
+            return Err(TransactionError::TxOutOfRange(range));
+        }
+        let is_dispute_family = matches!(
+            transaction.r#type,
+            TransactionType::Dispute | TransactionType::Resolve | TransactionType::Chargeback
+        );
+        // a client without an account has no transactions to dispute. rejecting this early means
+        // e.g. a dispute of another client's transaction doesn't leave an empty account behind
+        if is_dispute_family && !self.accounts.contains_key(&transaction.client) {
+            return Err(TransactionError::UnknownDisputeTarget);
+        }
+        if is_dispute_family
+            && self.options.reject_dispute_amounts
+            && let Some(amount) = transaction.amount
+        {
+            return Err(TransactionError::UnexpectedAmount(amount));
+        }
+        if matches!(
+            transaction.r#type,
+            TransactionType::Deposit | TransactionType::Withdrawal
+        ) && self.options.tx_scope == TxScope::Global
+            && self
+                .tx_owners
+                .get(&transaction.tx)
+                .is_some_and(|owner| *owner != transaction.client)
+        {
+            return Err(TransactionError::DuplicateTransactionId);
+        }
+        // e.g. a typo in the partner's data, which mustn't leave an empty account behind either
+        if let TransactionType::Unknown(token) = transaction.r#type {
+            return Err(TransactionError::UnknownType(token));
+        }
+        // the only transaction a locked account accepts. unlocking any other account, including
+        // one that doesn't exist, looks like an error on the partners side
+        if transaction.r#type == TransactionType::Unlock {
+            let account = self
+                .accounts
+                .get_mut(&transaction.client)
+                .filter(|account| account.locked)
+                .ok_or(TransactionError::AccountNotLocked)?;
+            account.locked = false;
+            if let Some(activity) = self.activity.get_mut(&transaction.client) {
+                activity.locked_by = None;
+            }
+            return Ok(());
+        }
+        if let Some(min_unit) = self.options.min_unit
+            && !is_dispute_family
+            && let Some(amount) = transaction.amount
+            && !(amount % min_unit).is_zero()
+        {
+            match self.options.min_unit_mode {
+                MinUnitMode::Reject => {
+                    return Err(TransactionError::SubUnitAmount { amount, min_unit });
+                }
+                MinUnitMode::Round => {
+                    let rounded = (amount / min_unit).round() * min_unit;
+                    self.rounded_amounts.push(RoundedAmount {
+                        client: transaction.client,
+                        tx: transaction.tx,
+                        amount,
+                        rounded,
+                    });
+                    transaction.amount = Some(rounded);
+                }
+            }
         }
 
         let account = self
             .accounts
-            .get_mut(&transaction.client)
-            .expect("to have an account for the client in our map");
+            .entry(transaction.client)
+            .or_insert_with(|| Account {
+                client: transaction.client,
+                available: Decimal::ZERO,
+                held: Decimal::ZERO,
+                total: Decimal::ZERO,
+                locked: false,
+            });
         // the referenced account is locked so we don't perform any operations on it
         if account.locked {
-            return;
+            return Err(TransactionError::AccountLocked);
         }
 
+        let key = (transaction.client, transaction.tx);
         match transaction.r#type {
             TransactionType::Deposit => {
                 // deposit transactions must specify an amount. if they don't, it looks like an error on the partners side
                 if transaction.amount.is_none() {
-                    return;
+                    return Err(TransactionError::MissingAmount);
                 }
 
                 let amount = transaction.amount.unwrap();
-                account.available += amount;
-                account.total += amount;
+                let is_split_deposit = self.disputable_transactions.contains_key(&key);
+                // reusing the id would replace the transaction a later dispute refers to
+                if is_split_deposit && !self.options.sum_duplicate_tx {
+                    return Err(TransactionError::DuplicateTransactionId);
+                }
+                if is_split_deposit {
+                    let mut split_transaction = self
+                        .disputable_transactions
+                        .get(&key)
+                        .map_err(TransactionError::spill_failed)?
+                        .expect("to have the split transaction in our map");
+                    // only untouched deposits can be merged. changing the amount of a disputed
+                    // transaction would make the held funds inconsistent
+                    if split_transaction.r#type != TransactionType::Deposit
+                        || self.dispute_states.contains_key(&key)
+                        || self.reversed_transactions.contains(&key)
+                    {
+                        return Err(TransactionError::SplitDepositRejected);
+                    }
+
+                    let split_amount = split_transaction
+                        .amount
+                        .expect("deposits to have an amount");
+                    let summed = match split_amount.checked_add(amount) {
+                        Some(summed) => summed,
+                        None if self.options.overflow_mode == OverflowMode::Saturate => {
+                            split_amount.saturating_add(amount)
+                        }
+                        None => return Err(TransactionError::BalanceOverflow),
+                    };
+                    split_transaction.amount = Some(summed);
+                    transaction = split_transaction;
+                }
+
+                let change = BalanceChange {
+                    available: amount,
+                    held: Decimal::ZERO,
+                    total: amount,
+                }
+                .checked(account, self.options.overflow_mode)?;
+                // stored before the balances change, so a failing spill file leaves both untouched
                 self.disputable_transactions
-                    .insert(transaction.tx, transaction);
+                    .insert(key, transaction)
+                    .map_err(TransactionError::spill_failed)?;
+                let overdraft = -account.available;
+                change.add_to(account);
+                let activity = self.activity.entry(key.0).or_default();
+                activity.deposited = activity.deposited.saturating_add(amount);
+                if overdraft > Decimal::ZERO {
+                    activity.overdraft_covered = activity
+                        .overdraft_covered
+                        .saturating_add(amount.min(overdraft));
+                }
+                if self.options.tx_scope == TxScope::Global {
+                    self.tx_owners.insert(key.1, key.0);
+                }
             }
             TransactionType::Withdrawal => {
                 // withdrawal transactions must specify an amount. if they don't, it looks like an error on the partners side
                 if transaction.amount.is_none() {
-                    return;
+                    return Err(TransactionError::MissingAmount);
                 }
 
                 let amount = transaction.amount.unwrap();
+                if self.disputable_transactions.contains_key(&key) {
+                    return Err(TransactionError::DuplicateTransactionId);
+                }
                 if amount > account.available {
                     // the account doesn't have enough funds to withdraw so we don't perform any operations on it
-                    return;
+                    return Err(TransactionError::InsufficientFunds);
                 }
 
-                account.available -= amount;
-                account.total -= amount;
+                let change = BalanceChange {
+                    available: -amount,
+                    held: Decimal::ZERO,
+                    total: -amount,
+                }
+                .checked(account, self.options.overflow_mode)?;
                 self.disputable_transactions
-                    .insert(transaction.tx, transaction);
+                    .insert(key, transaction)
+                    .map_err(TransactionError::spill_failed)?;
+                change.add_to(account);
+                let activity = self.activity.entry(key.0).or_default();
+                activity.withdrawn = activity.withdrawn.saturating_add(amount);
+                if self.options.tx_scope == TxScope::Global {
+                    self.tx_owners.insert(key.1, key.0);
+                }
             }
-            TransactionType::Dispute => {
-                let disputed_transaction = self.disputable_transactions.get(&transaction.tx);
-                // we don't have a transaction for this dispute so it looks like an error on the partners side
-                if disputed_transaction.is_none() {
-                    return;
+            TransactionType::Dispute | TransactionType::Resolve | TransactionType::Chargeback => {
+                let disputed_transaction = self
+                    .disputable_transactions
+                    .get(&key)
+                    .map_err(TransactionError::spill_failed)?;
+                // we don't have a transaction of this client for this dispute so it looks like an error on the partners side
+                if disputed_transaction.is_none() || self.reversed_transactions.contains(&key) {
+                    return Err(TransactionError::UnknownDisputeTarget);
                 }
 
                 let disputed_transaction = disputed_transaction.unwrap();
-                // the client of the disputed transaction must be the same as the account we're recording the dispute for
-                if disputed_transaction.client != transaction.client {
-                    return;
-                }
                 let amount = if let Some(amount) = disputed_transaction.amount {
                     amount
                 } else {
                     // disputable transactions must have an amount. if they don't, it looks like an error on the partners side
-                    return;
+                    return Err(TransactionError::MissingAmount);
                 };
-                account.available -= amount;
-                account.held += amount;
-                self.disputed_transaction_ids.insert(transaction.tx);
-            }
-            TransactionType::Resolve => {
-                // the transaction is not under dispute so it looks like an error on the partners side
-                if !self.disputed_transaction_ids.contains(&transaction.tx) {
-                    return;
-                }
 
-                // the transaction is already resolved so it looks like an error on the partners side
-                if self.resolved_dispute_ids.contains(&transaction.tx) {
-                    return;
+                if self.options.verify_dispute_amounts
+                    && let Some(actual) = transaction.amount
+                    && actual != amount
+                {
+                    return Err(TransactionError::DisputeAmountMismatch {
+                        expected: amount,
+                        actual,
+                    });
                 }
 
-                let resolved_transaction = self.disputable_transactions.get(&transaction.tx);
-                // we don't have a transaction for this resolve so it looks like an error on the partners side
-                if resolved_transaction.is_none() {
-                    return;
-                }
+                let funds = match (
+                    &disputed_transaction.r#type,
+                    self.options.withdrawal_dispute_model,
+                ) {
+                    (TransactionType::Withdrawal, WithdrawalDisputeModel::Refund) => {
+                        DisputedFunds::WithdrawalRefund
+                    }
+                    (TransactionType::Withdrawal, WithdrawalDisputeModel::Reversal) => {
+                        DisputedFunds::WithdrawalReversal
+                    }
+                    _ => DisputedFunds::Deposit,
+                };
 
-                let resolved_transaction = resolved_transaction.unwrap();
-                // the client of the resolved transaction must be the same as the account we're recording the resolve for
-                if resolved_transaction.client != transaction.client {
-                    return;
+                let state = self.dispute_states.get(&key).copied().unwrap_or_default();
+                if state == DisputeState::None
+                    && transaction.r#type == TransactionType::Dispute
+                    && let Some(limit) = self.options.max_tracked_disputes
+                    && self.dispute_states.len() >= limit
+                {
+                    return Err(TransactionError::DisputeLimitReached { limit });
                 }
-
-                let amount = if let Some(amount) = resolved_transaction.amount {
-                    amount
-                } else {
-                    // disputable transactions must have an amount. if they don't, it looks like an error on the partners side
-                    return;
+                // e.g. a resolve for a transaction that isn't under dispute, so it looks like an error on the partners side
+                let Some(next_state) = state.transition(&transaction.r#type) else {
+                    return Err(TransactionError::InvalidDisputeTransition {
+                        state,
+                        r#type: transaction.r#type,
+                    });
                 };
-                account.held -= amount;
-                account.available += amount;
-                self.resolved_dispute_ids.insert(transaction.tx);
-            }
-            TransactionType::Chargeback => {
-                // the transaction is not under dispute so it looks like an error on the partners side
-                if !self.disputed_transaction_ids.contains(&transaction.tx) {
-                    return;
-                }
-                let disputed_transaction = self.disputable_transactions.get(&transaction.tx);
-                if disputed_transaction.is_none() {
-                    return;
-                }
 
-                let disputed_transaction = disputed_transaction.unwrap();
-                // the client of the disputed transaction must be the same as the account we're recording the dispute for
-                if disputed_transaction.client != transaction.client {
-                    return;
+                match transaction.r#type {
+                    TransactionType::Dispute => {
+                        funds
+                            .dispute(amount)
+                            .apply(account, self.options.overflow_mode)?;
+                        let activity = self.activity.entry(transaction.client).or_default();
+                        activity.disputes += 1;
+                        activity.disputed_volume = activity.disputed_volume.saturating_add(amount);
+                    }
+                    TransactionType::Resolve => {
+                        // never release more than is held, whatever was disputed
+                        let released = match funds {
+                            DisputedFunds::WithdrawalReversal => amount,
+                            _ => amount.min(account.held.max(Decimal::ZERO)),
+                        };
+                        funds
+                            .resolve(released)
+                            .apply(account, self.options.overflow_mode)?;
+                        if released != amount {
+                            self.clamped_resolves.push(ClampedResolve {
+                                client: transaction.client,
+                                tx: transaction.tx,
+                                disputed: amount,
+                                released,
+                            });
+                        }
+                    }
+                    _ => {
+                        // the transaction is resolved but it's now being chargedback so to be safe, we undo the resolve and perform the chargeback
+                        let change = if state == DisputeState::Resolved {
+                            // undoing the resolve of a deposit takes the funds out of `available`
+                            // again. never take more than is left there, e.g. after a withdrawal
+                            let charged_back = match funds {
+                                DisputedFunds::Deposit => {
+                                    amount.min(account.available.max(Decimal::ZERO))
+                                }
+                                _ => amount,
+                            };
+                            if charged_back != amount {
+                                self.clamped_chargebacks.push(ClampedChargeback {
+                                    client: transaction.client,
+                                    tx: transaction.tx,
+                                    disputed: amount,
+                                    charged_back,
+                                });
+                            }
+                            funds
+                                .dispute(charged_back)
+                                .then(funds.chargeback(charged_back))
+                        } else {
+                            funds.chargeback(amount)
+                        };
+                        change.apply(account, self.options.overflow_mode)?;
+                        account.locked = true;
+                        self.activity
+                            .entry(transaction.client)
+                            .or_default()
+                            .locked_by = Some(transaction.tx);
+                        if self.options.prune_locked_accounts {
+                            self.disputable_transactions
+                                .remove_client(transaction.client);
+                        }
+                    }
                 }
+                self.dispute_states.insert(key, next_state);
+            }
+            TransactionType::Unlock | TransactionType::Unknown(_) => {
+                unreachable!(
+                    "unlocks and unknown types to be handled before the account is created"
+                )
+            }
+        }
 
-                let amount = if let Some(amount) = disputed_transaction.amount {
-                    amount
-                } else {
-                    // disputable transactions must have an amount. if they don't, it looks like an error on the partners side
-                    return;
-                };
+        Ok(())
+    }
 
-                // the transaction is resolved but it's now being chargedback so it... to be safe, we undo the resolve and perforom the chargeback
-                if self.resolved_dispute_ids.contains(&transaction.tx) {
-                    account.held += amount;
-                    account.available -= amount;
-                    self.resolved_dispute_ids.remove(&transaction.tx);
-                }
+    /// Forget all accounts and transactions to process another batch from scratch. The options
+    /// stay, and the collections keep their capacity.
+    pub fn reset(&mut self) {
+        self.accounts.clear();
+        self.disputable_transactions.clear();
+        self.tx_owners.clear();
+        self.dispute_states.clear();
+        self.reversed_transactions.clear();
+        self.clamped_resolves.clear();
+        self.clamped_chargebacks.clear();
+        self.rounded_amounts.clear();
+        self.activity.clear();
+    }
+
+    /// Record the transactions in the order they're given, e.g. from another source than the csv
+    /// readers. The rejected ones don't stop the processing, their errors are returned along with
+    /// their position in the input.
+    pub fn process_transactions<I: IntoIterator<Item = Transaction>>(
+        &mut self,
+        transactions: I,
+    ) -> Vec<(usize, TransactionError)> {
+        transactions
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, transaction)| {
+                self.record_transaction(transaction)
+                    .err()
+                    .map(|err| (idx, err))
+            })
+            .collect()
+    }
 
-                account.held -= amount;
-                account.total -= amount;
-                account.locked = true;
+    /// Resolve the open dispute of a transaction given only its id, for resolutions that arrive
+    /// without the client. Fails like a resolve if no transaction with this id is under dispute,
+    /// and resolves none if several clients have an open dispute of a transaction with this id.
+    pub fn resolve_tx(&mut self, tx: u32) -> Result<(), TransactionError> {
+        let mut clients = self
+            .dispute_states
+            .iter()
+            .filter(|((_, disputed_tx), state)| *disputed_tx == tx && **state == DisputeState::Open)
+            .map(|((client, _), _)| *client)
+            .collect::<Vec<_>>();
+        let client = match clients.as_slice() {
+            [] => return Err(TransactionError::UnknownDisputeTarget),
+            [client] => *client,
+            _ => {
+                clients.sort_unstable();
+                return Err(TransactionError::AmbiguousDisputeTarget(clients));
             }
-            _ => (),
+        };
+
+        self.record_transaction(Transaction {
+            r#type: TransactionType::Resolve,
+            client,
+            tx,
+            amount: None,
+            timestamp: None,
+        })
+    }
+
+    /// Undo the balance effect of a deposit or withdrawal of a client outside of the dispute flow,
+    /// e.g. to correct a fraudulently applied deposit. A reversed transaction can't be disputed
+    /// anymore, so a transaction under an open dispute has to be resolved or charged back first,
+    /// otherwise its held funds could never be released.
+    pub fn apply_reversal(&mut self, client: u16, tx: u32) -> Result<(), ReversalError> {
+        let key = (client, tx);
+        let transaction = self
+            .disputable_transactions
+            .get(&key)
+            .map_err(|err| ReversalError::SpillFailed(err.to_string()))?
+            .ok_or(ReversalError::UnknownTransaction(tx))?;
+        if self.reversed_transactions.contains(&key) {
+            return Err(ReversalError::AlreadyReversed(tx));
+        }
+        if self.dispute_states.get(&key) == Some(&DisputeState::Open) {
+            return Err(ReversalError::UnderDispute(tx));
+        }
+
+        let account = self
+            .accounts
+            .get_mut(&transaction.client)
+            .expect("the account of an applied transaction to exist");
+        if account.locked {
+            return Err(ReversalError::AccountLocked {
+                client: account.client,
+            });
         }
+
+        let amount = transaction
+            .amount
+            .expect("deposits and withdrawals to have an amount");
+        let amount = match transaction.r#type {
+            TransactionType::Deposit => -amount,
+            _ => amount,
+        };
+        BalanceChange {
+            available: amount,
+            held: Decimal::ZERO,
+            total: amount,
+        }
+        .apply(account, self.options.overflow_mode)
+        .map_err(|_| ReversalError::BalanceOverflow {
+            client: account.client,
+        })?;
+        self.reversed_transactions.insert(key);
+        Ok(())
+    }
+
+    /// Keep at most `memory_limit` disputable transactions in memory and spill the rest to a file
+    /// in `dir`. Disputes of spilled transactions work the same, they're just slower to look up.
+    pub fn spill_to(&mut self, dir: &Path, memory_limit: usize) -> io::Result<()> {
+        self.disputable_transactions.spill_to(dir, memory_limit)
     }
 
     pub fn summary(&self) -> &HashMap<u16, Account> {
         &self.accounts
     }
+
+    /// The account of a client, `None` if the client never had a transaction applied.
+    pub fn account(&self, client: u16) -> Option<&Account> {
+        self.accounts.get(&client)
+    }
+
+    /// The accounts ordered by client, so the output is the same across runs.
+    pub fn summary_sorted(&self) -> Vec<&Account> {
+        self.summary_sorted_by(SummaryOrder::Client)
+    }
+
+    pub fn summary_sorted_by(&self, order: SummaryOrder) -> Vec<&Account> {
+        let mut accounts = self.accounts.values().collect::<Vec<_>>();
+        match order {
+            SummaryOrder::Client => accounts.sort_unstable_by_key(|account| account.client),
+            SummaryOrder::DisputesDesc => accounts.sort_unstable_by_key(|account| {
+                let disputes = self
+                    .activity(account.client)
+                    .map_or(0, |activity| activity.disputes);
+                (Reverse(disputes), account.client)
+            }),
+        }
+        accounts
+    }
+
+    /// The rows of the summary output with the given optional columns in the given order.
+    pub fn summary_rows(
+        &self,
+        columns: SummaryColumns,
+        order: SummaryOrder,
+    ) -> impl Iterator<Item = SummaryRow<'_>> {
+        let open_dispute_txs = if columns.open_disputes {
+            self.open_dispute_txs()
+        } else {
+            HashMap::new()
+        };
+        self.summary_sorted_by(order)
+            .into_iter()
+            .map(move |account| {
+                let txs = open_dispute_txs
+                    .get(&account.client)
+                    .map_or(&[][..], Vec::as_slice);
+                self.row(account, columns, txs)
+            })
+    }
+
+    pub fn summary_row(&self, client: u16, columns: SummaryColumns) -> Option<SummaryRow<'_>> {
+        let account = self.accounts.get(&client)?;
+        let open_dispute_txs = if columns.open_disputes {
+            self.open_dispute_txs().remove(&client).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        Some(self.row(account, columns, &open_dispute_txs))
+    }
+
+    fn row<'a>(
+        &self,
+        account: &'a Account,
+        columns: SummaryColumns,
+        open_dispute_txs: &[u32],
+    ) -> SummaryRow<'a> {
+        if columns == SummaryColumns::default() {
+            SummaryRow::Standard(account)
+        } else {
+            SummaryRow::Wide(WideAccount::new(
+                account,
+                columns,
+                self.activity(account.client),
+                open_dispute_txs,
+            ))
+        }
+    }
+
+    /// The transactions under dispute at the moment by client, in ascending order.
+    pub fn open_dispute_txs(&self) -> HashMap<u16, Vec<u32>> {
+        let mut open_dispute_txs = HashMap::<u16, Vec<u32>>::new();
+        for ((client, tx), state) in &self.dispute_states {
+            if *state == DisputeState::Open {
+                open_dispute_txs.entry(*client).or_default().push(*tx);
+            }
+        }
+        for txs in open_dispute_txs.values_mut() {
+            txs.sort_unstable();
+        }
+        open_dispute_txs
+    }
+
+    /// Number of transactions under dispute at the moment, across all clients.
+    pub fn open_dispute_count(&self) -> usize {
+        self.dispute_state_count(DisputeState::Open)
+    }
+
+    /// Number of transactions whose latest dispute was resolved, across all clients. A resolved
+    /// transaction that is disputed again counts as open instead.
+    pub fn resolved_dispute_count(&self) -> usize {
+        self.dispute_state_count(DisputeState::Resolved)
+    }
+
+    fn dispute_state_count(&self, state: DisputeState) -> usize {
+        self.dispute_states
+            .values()
+            .filter(|dispute_state| **dispute_state == state)
+            .count()
+    }
+
+    /// Cumulative amount that was ever put under dispute for a client, including disputes that
+    /// were resolved or charged back since.
+    pub fn disputed_volume(&self, client: u16) -> Decimal {
+        self.activity
+            .get(&client)
+            .map(|activity| activity.disputed_volume)
+            .unwrap_or_default()
+    }
+
+    /// Whether any dispute of the client was applied, regardless of how it ended.
+    pub fn had_dispute(&self, client: u16) -> bool {
+        self.activity
+            .get(&client)
+            .is_some_and(|activity| activity.disputes > 0)
+    }
+
+    /// Aggregates of what happened to the account of a client, if it has any applied transactions.
+    pub fn activity(&self, client: u16) -> Option<&AccountActivity> {
+        self.activity.get(&client)
+    }
+
+    /// The account of the client if its available funds exceed its total, i.e. it holds a negative
+    /// amount. Disputes only ever hold positive amounts and resolves release at most what's held,
+    /// so this points to a sign bug.
+    pub fn invariant_violation(&self, client: u16) -> Option<&Account> {
+        self.accounts
+            .get(&client)
+            .filter(|account| account.available > account.total)
+    }
+
+    /// Check that the balances of every account add up, i.e. `available + held == total`. The
+    /// clients whose balances drifted apart are returned in ascending order.
+    pub fn verify_invariants(&self) -> Result<(), Vec<u16>> {
+        let mut inconsistent_clients = self
+            .accounts
+            .values()
+            .filter(|account| account.available + account.held != account.total)
+            .map(|account| account.client)
+            .collect::<Vec<_>>();
+        if inconsistent_clients.is_empty() {
+            return Ok(());
+        }
+        inconsistent_clients.sort_unstable();
+        Err(inconsistent_clients)
+    }
+
+    /// Count the accounts by their total balance into the buckets split by the bounds.
+    pub fn balance_histogram(&self, bounds: Vec<Decimal>) -> BalanceHistogram {
+        self.accounts
+            .values()
+            .map(|account| account.total)
+            .fold(BalanceHistogram::new(bounds), BalanceHistogram::observe)
+    }
+
+    /// Locked accounts that still hold funds of other open disputes. Those disputes can never be
+    /// resolved or charged back since a locked account doesn't accept any transactions, so the
+    /// held funds are stuck. Ordered by client.
+    pub fn stuck_locked_accounts(&self) -> Vec<&Account> {
+        let mut accounts = self
+            .accounts
+            .values()
+            .filter(|account| account.locked && account.held > Decimal::ZERO)
+            .collect::<Vec<_>>();
+        accounts.sort_by_key(|account| account.client);
+        accounts
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+    };
+
     use super::*;
 
     #[test]
-    fn test_withdrawal_with_insufficient_funds() {
+    fn test_dispute_of_other_client_creates_no_account() {
         let mut service = AccountService::new();
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Deposit,
-            client: 1,
-            tx: 1,
-            amount: Some(Decimal::from(50)),
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Withdrawal,
-            client: 1,
-            tx: 2,
-            amount: Some(Decimal::from(100)),
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            service.record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 2,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            }),
+            Err(TransactionError::UnknownDisputeTarget)
+        );
+        assert!(!service.summary().contains_key(&2));
+        assert_eq!(service.summary()[&1].held, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_unknown_type() {
+        let mut service = AccountService::new();
+
+        assert_eq!(
+            service.record_transaction(Transaction {
+                r#type: TransactionType::Unknown("depositt".to_string()),
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            }),
+            Err(TransactionError::UnknownType("depositt".to_string()))
+        );
+        assert_eq!(service.account(1), None);
+    }
+
+    #[test]
+    fn test_process_transactions() {
+        let transaction = |r#type, client, tx, amount: Option<i64>| Transaction {
+            r#type,
+            client,
+            tx,
+            amount: amount.map(Decimal::from),
+            timestamp: None,
+        };
+        let mut service = AccountService::new();
+
+        let errors = service.process_transactions(vec![
+            transaction(TransactionType::Deposit, 1, 1, Some(10)),
+            transaction(TransactionType::Deposit, 2, 2, Some(5)),
+            transaction(TransactionType::Withdrawal, 2, 3, Some(8)),
+            transaction(TransactionType::Dispute, 1, 1, None),
+        ]);
+
+        assert_eq!(errors, vec![(2, TransactionError::InsufficientFunds)]);
+        let account = service.account(1).unwrap();
+        assert_eq!(account.available, Decimal::ZERO);
+        assert_eq!(account.held, Decimal::from(10));
+        assert_eq!(service.account(2).unwrap().available, Decimal::from(5));
+    }
+
+    #[test]
+    fn test_account() {
+        let mut service = AccountService::new();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+
+        let account = service.account(1).unwrap();
+        assert_eq!(account.client, 1);
+        assert_eq!(account.available, Decimal::from(50));
+        assert_eq!(service.account(2), None);
+    }
+
+    #[test]
+    fn test_apply_reversal() {
+        let mut service = AccountService::new();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 2,
+                amount: Some(Decimal::from(30)),
+                timestamp: None,
+            })
+            .unwrap();
+
+        assert_eq!(service.apply_reversal(1, 2), Ok(()));
+        let account = &service.summary()[&1];
+        assert_eq!(account.available, Decimal::from(50));
+        assert_eq!(account.total, Decimal::from(50));
+        assert_eq!(account.held, Decimal::ZERO);
+
+        assert_eq!(
+            service.apply_reversal(1, 2),
+            Err(ReversalError::AlreadyReversed(2))
+        );
+        assert_eq!(
+            service.apply_reversal(1, 3),
+            Err(ReversalError::UnknownTransaction(3))
+        );
+        assert_eq!(
+            service.apply_reversal(2, 1),
+            Err(ReversalError::UnknownTransaction(1))
+        );
+        assert_eq!(
+            service.record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            }),
+            Err(TransactionError::UnknownDisputeTarget)
+        );
+    }
+
+    #[test]
+    fn test_apply_reversal_picks_the_client() {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            tx_scope: TxScope::Client,
+            ..Default::default()
         });
+        for client in [1, 2] {
+            service
+                .record_transaction(Transaction {
+                    r#type: TransactionType::Deposit,
+                    client,
+                    tx: 7,
+                    amount: Some(Decimal::from(10)),
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+
+        assert_eq!(service.apply_reversal(2, 7), Ok(()));
+        assert_eq!(service.summary()[&1].total, Decimal::from(10));
+        assert_eq!(service.summary()[&2].total, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_apply_reversal_of_disputed_or_locked() {
+        let mut service = AccountService::new();
+        for (r#type, tx, amount) in [
+            (TransactionType::Deposit, 1, Some(Decimal::from(50))),
+            (TransactionType::Deposit, 2, Some(Decimal::from(30))),
+            (TransactionType::Dispute, 2, None),
+        ] {
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client: 1,
+                    tx,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+
+        assert_eq!(
+            service.apply_reversal(1, 2),
+            Err(ReversalError::UnderDispute(2))
+        );
+        assert_eq!(service.summary()[&1].held, Decimal::from(30));
+
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Chargeback,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(
+            service.apply_reversal(1, 1),
+            Err(ReversalError::AccountLocked { client: 1 })
+        );
+        assert_eq!(service.summary()[&1].total, Decimal::from(50));
+    }
+
+    #[test]
+    fn test_withdrawal_with_insufficient_funds() {
+        let mut service = AccountService::new();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(
+            service.record_transaction(Transaction {
+                r#type: TransactionType::Withdrawal,
+                client: 1,
+                tx: 2,
+                amount: Some(Decimal::from(100)),
+                timestamp: None,
+            }),
+            Err(TransactionError::InsufficientFunds)
+        );
 
         let account = service.summary().get(&1);
         assert!(account.is_some());
@@ -209,18 +1240,24 @@ mod tests {
     #[test]
     fn test_dispute() {
         let mut service = AccountService::new();
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Deposit,
-            client: 1,
-            tx: 1,
-            amount: Some(Decimal::from(50)),
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Dispute,
-            client: 1,
-            tx: 1,
-            amount: None,
-        });
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
 
         let account = service.summary().get(&1);
         assert!(account.is_some());
@@ -233,51 +1270,422 @@ mod tests {
     }
 
     #[test]
-    fn test_dispute_with_invalid_tx() {
-        let mut service = AccountService::new();
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Deposit,
-            client: 1,
-            tx: 1,
-            amount: Some(Decimal::from(50)),
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Dispute,
-            client: 1,
-            tx: 2,
-            amount: None,
+    fn test_resolve_tx_of_several_clients() {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            tx_scope: TxScope::Client,
+            ..Default::default()
         });
+        for (r#type, client, tx) in [
+            (TransactionType::Deposit, 1, 7),
+            (TransactionType::Deposit, 2, 7),
+            (TransactionType::Deposit, 2, 8),
+            (TransactionType::Dispute, 1, 7),
+            (TransactionType::Dispute, 2, 7),
+            (TransactionType::Dispute, 2, 8),
+        ] {
+            let amount = (r#type == TransactionType::Deposit).then(|| Decimal::from(10));
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client,
+                    tx,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
 
-        let account = service.summary().get(&1);
-        assert!(account.is_some());
-        let account = account.unwrap();
-        assert_eq!(account.available, Decimal::from(50));
-        assert_eq!(account.total, Decimal::from(50));
-        assert_eq!(account.held, Decimal::ZERO);
-        assert_eq!(account.locked, false);
+        assert_eq!(
+            service.resolve_tx(7),
+            Err(TransactionError::AmbiguousDisputeTarget(vec![1, 2]))
+        );
+        assert_eq!(service.open_dispute_count(), 3);
+        assert_eq!(service.resolve_tx(8), Ok(()));
+        assert_eq!(service.dispute_states[&(2, 8)], DisputeState::Resolved);
     }
 
     #[test]
-    fn test_dispute_with_resolve() {
+    fn test_resolve_is_clamped_to_held_funds() {
         let mut service = AccountService::new();
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Deposit,
-            client: 1,
-            tx: 1,
-            amount: Some(Decimal::from(50)),
-        });
-        service.record_transaction(Transaction {
+        for (r#type, tx, amount) in [
+            (TransactionType::Deposit, 1, Some(Decimal::from(50))),
+            (TransactionType::Deposit, 2, Some(Decimal::from(20))),
+            (TransactionType::Dispute, 1, None),
+            (TransactionType::Dispute, 2, None),
+        ] {
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client: 1,
+                    tx,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+        // pretend the held funds went out of sync with the open disputes
+        service.accounts.get_mut(&1).unwrap().held = Decimal::from(30);
+
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert!(service.clamped_resolves.is_empty());
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+
+        let account = &service.summary()[&1];
+        assert_eq!(account.held, Decimal::ZERO);
+        assert_eq!(account.available, Decimal::from(30));
+        assert_eq!(
+            service.clamped_resolves,
+            vec![ClampedResolve {
+                client: 1,
+                tx: 1,
+                disputed: Decimal::from(50),
+                released: Decimal::from(10),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_chargeback_after_resolve_and_withdrawal() {
+        let mut service = AccountService::new();
+        for (r#type, tx, amount) in [
+            (TransactionType::Deposit, 1, Some(Decimal::from(50))),
+            (TransactionType::Dispute, 1, None),
+            (TransactionType::Resolve, 1, None),
+            (TransactionType::Withdrawal, 2, Some(Decimal::from(30))),
+            (TransactionType::Chargeback, 1, None),
+        ] {
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client: 1,
+                    tx,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+
+        let account = &service.summary()[&1];
+        assert_eq!(account.available, Decimal::ZERO);
+        assert_eq!(account.held, Decimal::ZERO);
+        assert_eq!(account.total, Decimal::ZERO);
+        assert!(account.locked);
+        assert_eq!(
+            service.clamped_chargebacks,
+            vec![ClampedChargeback {
+                client: 1,
+                tx: 1,
+                disputed: Decimal::from(50),
+                charged_back: Decimal::from(20),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_dispute_counts() {
+        let mut service = AccountService::new();
+        assert_eq!(service.open_dispute_count(), 0);
+        assert_eq!(service.resolved_dispute_count(), 0);
+        for (r#type, client, tx) in [
+            (TransactionType::Deposit, 1, 1),
+            (TransactionType::Deposit, 2, 2),
+            (TransactionType::Deposit, 2, 3),
+            (TransactionType::Dispute, 1, 1),
+            (TransactionType::Dispute, 2, 2),
+            (TransactionType::Resolve, 1, 1),
+        ] {
+            let amount = (r#type == TransactionType::Deposit).then(|| Decimal::from(10));
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client,
+                    tx,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+
+        assert_eq!(service.open_dispute_count(), 1);
+        assert_eq!(service.resolved_dispute_count(), 1);
+    }
+
+    #[test]
+    fn test_open_dispute_txs() {
+        let mut service = AccountService::new();
+        for (r#type, client, tx) in [
+            (TransactionType::Deposit, 1, 1),
+            (TransactionType::Deposit, 1, 2),
+            (TransactionType::Deposit, 1, 3),
+            (TransactionType::Deposit, 2, 4),
+            (TransactionType::Dispute, 1, 3),
+            (TransactionType::Dispute, 1, 1),
+            (TransactionType::Dispute, 1, 2),
+            (TransactionType::Resolve, 1, 2),
+        ] {
+            let amount = (r#type == TransactionType::Deposit).then_some(Decimal::ONE);
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client,
+                    tx,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+
+        let columns = SummaryColumns {
+            open_disputes: true,
+            ..Default::default()
+        };
+        let open_dispute_txs = |client| match service.summary_row(client, columns) {
+            Some(SummaryRow::Wide(account)) => account.open_dispute_txs,
+            row => panic!("unexpected row {row:?}"),
+        };
+        assert_eq!(open_dispute_txs(1).as_deref(), Some("1;3"));
+        assert_eq!(open_dispute_txs(2).as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_dispute_twice() {
+        let mut service = AccountService::new();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        let dispute = Transaction {
             r#type: TransactionType::Dispute,
             client: 1,
             tx: 1,
             amount: None,
+            timestamp: None,
+        };
+        service.record_transaction(dispute.clone()).unwrap();
+        assert_eq!(
+            service.record_transaction(dispute),
+            Err(TransactionError::InvalidDisputeTransition {
+                state: DisputeState::Open,
+                r#type: TransactionType::Dispute,
+            })
+        );
+
+        let account = &service.summary()[&1];
+        assert_eq!(account.available, Decimal::ZERO);
+        assert_eq!(account.held, Decimal::from(50));
+        assert_eq!(service.disputed_volume(1), Decimal::from(50));
+    }
+
+    #[test]
+    fn test_duplicate_transaction_id() {
+        let mut service = AccountService::new();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        for (r#type, amount) in [
+            (TransactionType::Deposit, Decimal::from(20)),
+            (TransactionType::Withdrawal, Decimal::from(5)),
+        ] {
+            assert_eq!(
+                service.record_transaction(Transaction {
+                    r#type,
+                    client: 1,
+                    tx: 1,
+                    amount: Some(amount),
+                    timestamp: None,
+                }),
+                Err(TransactionError::DuplicateTransactionId)
+            );
+        }
+        assert_eq!(service.summary()[&1].total, Decimal::from(50));
+
+        // the original deposit is still the one that gets disputed
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(service.summary()[&1].held, Decimal::from(50));
+    }
+
+    #[test]
+    fn test_max_tracked_disputes() {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            max_tracked_disputes: Some(1),
+            ..Default::default()
         });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Resolve,
+        for tx in [1, 2] {
+            service
+                .record_transaction(Transaction {
+                    r#type: TransactionType::Deposit,
+                    client: 1,
+                    tx,
+                    amount: Some(Decimal::from(10)),
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+        let dispute = |tx| Transaction {
+            r#type: TransactionType::Dispute,
             client: 1,
-            tx: 1,
+            tx,
             amount: None,
+            timestamp: None,
+        };
+
+        service.record_transaction(dispute(1)).unwrap();
+        assert_eq!(
+            service.record_transaction(dispute(2)),
+            Err(TransactionError::DisputeLimitReached { limit: 1 })
+        );
+        assert_eq!(service.summary()[&1].held, Decimal::from(10));
+
+        // the tracked dispute can still move on
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                ..dispute(1)
+            })
+            .unwrap();
+        service.record_transaction(dispute(1)).unwrap();
+    }
+
+    #[test]
+    fn test_bounded_disputable_transactions() {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            prune_locked_accounts: true,
+            max_disputable_transactions: Some(100),
+            ..Default::default()
         });
+        for tx in 0..10_000 {
+            service
+                .record_transaction(Transaction {
+                    r#type: TransactionType::Deposit,
+                    client: (tx % 10) as u16,
+                    tx,
+                    amount: Some(Decimal::ONE),
+                    timestamp: None,
+                })
+                .unwrap();
+            assert!(service.disputable_transactions.len() <= 100);
+        }
+
+        // evicted transactions can't be disputed anymore
+        let dispute = |client, tx| Transaction {
+            r#type: TransactionType::Dispute,
+            client,
+            tx,
+            amount: None,
+            timestamp: None,
+        };
+        assert_eq!(
+            service.record_transaction(dispute(0, 0)),
+            Err(TransactionError::UnknownDisputeTarget)
+        );
+
+        service.record_transaction(dispute(9, 9_999)).unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Chargeback,
+                ..dispute(9, 9_999)
+            })
+            .unwrap();
+        assert_eq!(service.disputable_transactions.len(), 90);
+    }
+
+    #[test]
+    fn test_dispute_with_invalid_tx() {
+        let mut service = AccountService::new();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(
+            service.record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            }),
+            Err(TransactionError::UnknownDisputeTarget)
+        );
+
+        let account = service.summary().get(&1);
+        assert!(account.is_some());
+        let account = account.unwrap();
+        assert_eq!(account.available, Decimal::from(50));
+        assert_eq!(account.total, Decimal::from(50));
+        assert_eq!(account.held, Decimal::ZERO);
+        assert_eq!(account.locked, false);
+    }
+
+    #[test]
+    fn test_dispute_with_resolve() {
+        let mut service = AccountService::new();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
 
         let account = service.summary().get(&1);
         assert!(account.is_some());
@@ -289,27 +1697,117 @@ mod tests {
         assert_eq!(account.locked, false);
     }
 
+    #[test]
+    fn test_dispute_after_resolve() {
+        let mut service = AccountService::new();
+        for r#type in [
+            TransactionType::Deposit,
+            TransactionType::Dispute,
+            TransactionType::Resolve,
+            TransactionType::Dispute,
+        ] {
+            let amount = (r#type == TransactionType::Deposit).then_some(Decimal::from(50));
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client: 1,
+                    tx: 1,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+
+        let account = &service.summary()[&1];
+        assert_eq!(account.available, Decimal::ZERO);
+        assert_eq!(account.held, Decimal::from(50));
+        assert_eq!(account.total, Decimal::from(50));
+        assert_eq!(service.dispute_states[&(1, 1)], DisputeState::Open);
+        assert_eq!(service.open_dispute_txs()[&1], vec![1]);
+    }
+
+    #[test]
+    fn test_repeated_dispute_resolve_cycles() {
+        for model in [
+            WithdrawalDisputeModel::Refund,
+            WithdrawalDisputeModel::Reversal,
+        ] {
+            let mut service = AccountService::with_options(AccountServiceOptions {
+                withdrawal_dispute_model: model,
+                ..Default::default()
+            });
+            for (r#type, tx, amount) in [
+                (TransactionType::Deposit, 1, 50),
+                (TransactionType::Withdrawal, 2, 20),
+            ] {
+                service
+                    .record_transaction(Transaction {
+                        r#type,
+                        client: 1,
+                        tx,
+                        amount: Some(Decimal::from(amount)),
+                        timestamp: None,
+                    })
+                    .unwrap();
+            }
+
+            for cycle in 1..=5 {
+                for tx in [1, 2] {
+                    for r#type in [TransactionType::Dispute, TransactionType::Resolve] {
+                        service
+                            .record_transaction(Transaction {
+                                r#type,
+                                client: 1,
+                                tx,
+                                amount: None,
+                                timestamp: None,
+                            })
+                            .unwrap();
+                    }
+                }
+
+                let account = &service.summary()[&1];
+                assert_eq!(account.available, Decimal::from(30), "{model:?} {cycle}");
+                assert_eq!(account.held, Decimal::ZERO, "{model:?} {cycle}");
+                assert_eq!(account.total, Decimal::from(30), "{model:?} {cycle}");
+                assert_eq!(service.activity(1).unwrap().disputes, cycle * 2);
+            }
+            assert!(service.clamped_resolves.is_empty());
+            assert!(service.open_dispute_txs().is_empty());
+        }
+    }
+
     #[test]
     fn test_dispute_with_resolve_on_invalid_tx() {
         let mut service = AccountService::new();
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Deposit,
-            client: 1,
-            tx: 1,
-            amount: Some(Decimal::from(50)),
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Dispute,
-            client: 1,
-            tx: 1,
-            amount: None,
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Resolve,
-            client: 1,
-            tx: 2,
-            amount: None,
-        });
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(
+            service.record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            }),
+            Err(TransactionError::UnknownDisputeTarget)
+        );
 
         let account = service.summary().get(&1);
         assert!(account.is_some());
@@ -324,24 +1822,33 @@ mod tests {
     #[test]
     fn test_chargeback() {
         let mut service = AccountService::new();
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Deposit,
-            client: 1,
-            tx: 1,
-            amount: Some(Decimal::from(50)),
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Dispute,
-            client: 1,
-            tx: 1,
-            amount: None,
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Chargeback,
-            client: 1,
-            tx: 1,
-            amount: None,
-        });
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Chargeback,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
 
         let account = service.summary().get(&1);
         assert!(account.is_some());
@@ -356,30 +1863,42 @@ mod tests {
     #[test]
     fn test_chargeback_on_disputed_transaction_only() {
         let mut service = AccountService::new();
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Deposit,
-            client: 1,
-            tx: 1,
-            amount: Some(Decimal::from(50)),
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Deposit,
-            client: 1,
-            tx: 2,
-            amount: Some(Decimal::from(10)),
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Dispute,
-            client: 1,
-            tx: 1,
-            amount: None,
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Chargeback,
-            client: 1,
-            tx: 1,
-            amount: None,
-        });
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 2,
+                amount: Some(Decimal::from(10)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Chargeback,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
 
         let account = service.summary().get(&1);
         assert!(account.is_some());
@@ -394,38 +1913,975 @@ mod tests {
     #[test]
     fn test_revert_of_resolve_on_chargeback() {
         let mut service = AccountService::new();
-        service.record_transaction(Transaction {
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Chargeback,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+
+        let account = service.summary().get(&1);
+        assert!(account.is_some());
+
+        let account = account.unwrap();
+        assert_eq!(account.available, Decimal::ZERO);
+        assert_eq!(account.total, Decimal::ZERO);
+        assert_eq!(account.held, Decimal::ZERO);
+        assert_eq!(account.locked, true);
+    }
+
+    #[test]
+    fn test_order_only_matters_within_a_client() {
+        let deposit_1 = Transaction {
             r#type: TransactionType::Deposit,
             client: 1,
             tx: 1,
             amount: Some(Decimal::from(50)),
-        });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Dispute,
+            timestamp: None,
+        };
+        let withdrawal_1 = Transaction {
+            r#type: TransactionType::Withdrawal,
             client: 1,
-            tx: 1,
+            tx: 2,
+            amount: Some(Decimal::from(30)),
+            timestamp: None,
+        };
+        let deposit_2 = Transaction {
+            r#type: TransactionType::Deposit,
+            client: 2,
+            tx: 3,
+            amount: Some(Decimal::from(20)),
+            timestamp: None,
+        };
+        let dispute_2 = Transaction {
+            r#type: TransactionType::Dispute,
+            client: 2,
+            tx: 3,
             amount: None,
+            timestamp: None,
+        };
+
+        let balances = |transactions: Vec<&Transaction>| {
+            let mut service = AccountService::new();
+            for transaction in transactions {
+                let _ = service.record_transaction(transaction.clone());
+            }
+            let mut balances = service
+                .summary()
+                .values()
+                .map(|account| {
+                    (
+                        account.client,
+                        account.available,
+                        account.held,
+                        account.total,
+                        account.locked,
+                    )
+                })
+                .collect::<Vec<_>>();
+            balances.sort();
+            balances
+        };
+
+        let interleaved = balances(vec![&deposit_1, &deposit_2, &withdrawal_1, &dispute_2]);
+        let grouped = balances(vec![&deposit_2, &dispute_2, &deposit_1, &withdrawal_1]);
+        assert_eq!(interleaved, grouped);
+        assert_eq!(
+            interleaved,
+            vec![
+                (
+                    1,
+                    Decimal::from(20),
+                    Decimal::ZERO,
+                    Decimal::from(20),
+                    false
+                ),
+                (
+                    2,
+                    Decimal::ZERO,
+                    Decimal::from(20),
+                    Decimal::from(20),
+                    false
+                ),
+            ]
+        );
+
+        // the withdrawal now happens before the deposit so it fails due to insufficient funds
+        let reordered = balances(vec![&withdrawal_1, &deposit_2, &deposit_1, &dispute_2]);
+        assert_ne!(interleaved, reordered);
+        assert_eq!(
+            reordered[0],
+            (
+                1,
+                Decimal::from(50),
+                Decimal::ZERO,
+                Decimal::from(50),
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn test_global_tx_scope_rejects_other_clients_ids() {
+        let mut service = AccountService::new();
+        let transaction = |r#type, client| Transaction {
+            r#type,
+            client,
+            tx: 1,
+            amount: Some(Decimal::from(10)),
+            timestamp: None,
+        };
+        service
+            .record_transaction(transaction(TransactionType::Deposit, 1))
+            .unwrap();
+        for r#type in [TransactionType::Deposit, TransactionType::Withdrawal] {
+            assert_eq!(
+                service.record_transaction(transaction(r#type, 2)),
+                Err(TransactionError::DuplicateTransactionId)
+            );
+        }
+        assert_eq!(service.account(2), None);
+        assert_eq!(service.summary()[&1].total, Decimal::from(10));
+    }
+
+    #[test]
+    fn test_dispute_routes_to_the_clients_transaction() {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            tx_scope: TxScope::Client,
+            ..Default::default()
+        });
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 2,
+                tx: 1,
+                amount: Some(Decimal::from(20)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 2,
+                tx: 2,
+                amount: Some(Decimal::from(5)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 2,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        // client 3 doesn't own tx 2 so the dispute must not touch client 2
+        assert_eq!(
+            service.record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 3,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            }),
+            Err(TransactionError::UnknownDisputeTarget)
+        );
+
+        let account = service.summary().get(&1).unwrap();
+        assert_eq!(account.available, Decimal::from(50));
+        assert_eq!(account.held, Decimal::ZERO);
+
+        let account = service.summary().get(&2).unwrap();
+        assert_eq!(account.available, Decimal::from(5));
+        assert_eq!(account.held, Decimal::from(20));
+        assert_eq!(account.total, Decimal::from(25));
+    }
+
+    #[test]
+    fn test_sum_duplicate_tx() {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            sum_duplicate_tx: true,
+            ..Default::default()
+        });
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(10)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(5)),
+                timestamp: None,
+            })
+            .unwrap();
+
+        let disputable_transaction = service
+            .disputable_transactions
+            .get(&(1, 1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(disputable_transaction.amount, Some(Decimal::from(15)));
+
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        // the dispute is open so the amount of the transaction can't change anymore
+        assert_eq!(
+            service.record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(1)),
+                timestamp: None,
+            }),
+            Err(TransactionError::SplitDepositRejected)
+        );
+
+        let account = service.summary().get(&1).unwrap();
+        assert_eq!(account.available, Decimal::ZERO);
+        assert_eq!(account.held, Decimal::from(15));
+        assert_eq!(account.total, Decimal::from(15));
+    }
+
+    #[test]
+    fn test_deposit_covers_overdraft() {
+        let mut service = AccountService::new();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(20)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Withdrawal,
+                client: 1,
+                tx: 2,
+                amount: Some(Decimal::from(20)),
+                timestamp: None,
+            })
+            .unwrap();
+        // the deposit is disputed after its funds were withdrawn, which overdraws the account
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(
+            service.summary().get(&1).unwrap().available,
+            Decimal::from(-20)
+        );
+        assert_eq!(
+            service.activity(1).unwrap().overdraft_covered,
+            Decimal::ZERO
+        );
+
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 3,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+
+        let account = service.summary().get(&1).unwrap();
+        assert_eq!(account.available, Decimal::from(30));
+        assert_eq!(account.held, Decimal::from(20));
+        assert_eq!(account.total, Decimal::from(50));
+        assert_eq!(
+            service.activity(1).unwrap().overdraft_covered,
+            Decimal::from(20)
+        );
+    }
+
+    #[test]
+    fn test_disputed_volume() {
+        let mut service = AccountService::new();
+        for (tx, amount) in [(1, 10), (2, 25), (3, 100)] {
+            service
+                .record_transaction(Transaction {
+                    r#type: TransactionType::Deposit,
+                    client: 1,
+                    tx,
+                    amount: Some(Decimal::from(amount)),
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+
+        assert_eq!(service.disputed_volume(1), Decimal::from(35));
+        assert_eq!(service.disputed_volume(2), Decimal::ZERO);
+    }
+
+    /// Deposits 100, withdraws 40 and disputes the withdrawal.
+    fn disputed_withdrawal(model: WithdrawalDisputeModel) -> AccountService {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            withdrawal_dispute_model: model,
+            ..Default::default()
         });
-        service.record_transaction(Transaction {
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(100)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Withdrawal,
+                client: 1,
+                tx: 2,
+                amount: Some(Decimal::from(40)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        service
+    }
+
+    fn balances(service: &AccountService) -> (Decimal, Decimal, Decimal, bool) {
+        let account = service.summary().get(&1).unwrap();
+        (
+            account.available,
+            account.held,
+            account.total,
+            account.locked,
+        )
+    }
+
+    #[test]
+    fn test_withdrawal_dispute_refund_model() {
+        let service = disputed_withdrawal(WithdrawalDisputeModel::Refund);
+        assert_eq!(
+            balances(&service),
+            (
+                Decimal::from(60),
+                Decimal::from(40),
+                Decimal::from(100),
+                false
+            )
+        );
+
+        let mut resolved = disputed_withdrawal(WithdrawalDisputeModel::Refund);
+        resolved
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(
+            balances(&resolved),
+            (Decimal::from(60), Decimal::ZERO, Decimal::from(60), false)
+        );
+
+        let mut charged_back = disputed_withdrawal(WithdrawalDisputeModel::Refund);
+        charged_back
+            .record_transaction(Transaction {
+                r#type: TransactionType::Chargeback,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(
+            balances(&charged_back),
+            (Decimal::from(100), Decimal::ZERO, Decimal::from(100), true)
+        );
+    }
+
+    #[test]
+    fn test_withdrawal_dispute_reversal_model() {
+        let service = disputed_withdrawal(WithdrawalDisputeModel::Reversal);
+        assert_eq!(
+            balances(&service),
+            (Decimal::from(100), Decimal::ZERO, Decimal::from(100), false)
+        );
+
+        let mut resolved = disputed_withdrawal(WithdrawalDisputeModel::Reversal);
+        resolved
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(
+            balances(&resolved),
+            (Decimal::from(60), Decimal::ZERO, Decimal::from(60), false)
+        );
+
+        let mut charged_back = disputed_withdrawal(WithdrawalDisputeModel::Reversal);
+        charged_back
+            .record_transaction(Transaction {
+                r#type: TransactionType::Chargeback,
+                client: 1,
+                tx: 2,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(
+            balances(&charged_back),
+            (Decimal::from(100), Decimal::ZERO, Decimal::from(100), true)
+        );
+    }
+
+    #[test]
+    fn test_disputed_withdrawal_doesnt_reduce_available() {
+        for model in [
+            WithdrawalDisputeModel::Refund,
+            WithdrawalDisputeModel::Reversal,
+        ] {
+            let service = disputed_withdrawal(model);
+            let (available, held, total, _) = balances(&service);
+            // the withdrawn money already left, the dispute can only bring it back
+            assert!(available >= Decimal::from(60), "{model:?}");
+            assert_eq!(available + held, total, "{model:?}");
+            assert_eq!(total, Decimal::from(100), "{model:?}");
+        }
+    }
+
+    #[test]
+    fn test_stuck_locked_accounts() {
+        let mut service = AccountService::new();
+        for (client, tx) in [(1, 1), (1, 2), (2, 3)] {
+            service
+                .record_transaction(Transaction {
+                    r#type: TransactionType::Deposit,
+                    client,
+                    tx,
+                    amount: Some(Decimal::from(10)),
+                    timestamp: None,
+                })
+                .unwrap();
+            service
+                .record_transaction(Transaction {
+                    r#type: TransactionType::Dispute,
+                    client,
+                    tx,
+                    amount: None,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+        // both accounts get locked but only client 1 still has the open dispute of tx 2
+        for (client, tx) in [(1, 1), (2, 3)] {
+            service
+                .record_transaction(Transaction {
+                    r#type: TransactionType::Chargeback,
+                    client,
+                    tx,
+                    amount: None,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+
+        let stuck = service.stuck_locked_accounts();
+        assert_eq!(stuck.len(), 1);
+        assert_eq!(stuck[0].client, 1);
+        assert_eq!(stuck[0].held, Decimal::from(10));
+    }
+
+    #[test]
+    fn test_invariant_holds_through_disputes() {
+        let mut service = AccountService::new();
+        let sequence = [
+            (TransactionType::Deposit, 1, Some(Decimal::from(10))),
+            (TransactionType::Deposit, 2, Some(Decimal::from(5))),
+            (TransactionType::Withdrawal, 3, Some(Decimal::from(12))),
+            (TransactionType::Dispute, 1, None),
+            (TransactionType::Resolve, 1, None),
+            (TransactionType::Dispute, 2, None),
+            (TransactionType::Chargeback, 2, None),
+        ];
+        for (r#type, tx, amount) in sequence {
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client: 1,
+                    tx,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+            assert_eq!(service.invariant_violation(1), None);
+        }
+
+        // e.g. a resolve crediting available without releasing the held funds
+        let account = service.accounts.get_mut(&1).unwrap();
+        account.available += Decimal::ONE;
+        account.held -= Decimal::ONE;
+        assert_eq!(service.invariant_violation(1).unwrap().client, 1);
+    }
+
+    #[test]
+    fn test_verify_invariants() {
+        let mut service = AccountService::new();
+        for (r#type, client, tx, amount) in [
+            (TransactionType::Deposit, 1, 1, Some(Decimal::from(10))),
+            (TransactionType::Deposit, 2, 2, Some(Decimal::from(5))),
+            (TransactionType::Deposit, 3, 3, Some(Decimal::from(5))),
+            (TransactionType::Dispute, 1, 1, None),
+            (TransactionType::Dispute, 2, 2, None),
+            (TransactionType::Chargeback, 2, 2, None),
+        ] {
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client,
+                    tx,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+        assert_eq!(service.verify_invariants(), Ok(()));
+
+        service.accounts.get_mut(&3).unwrap().held += Decimal::ONE;
+        service.accounts.get_mut(&1).unwrap().total -= Decimal::ONE;
+        assert_eq!(service.verify_invariants(), Err(vec![1, 3]));
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let service = Arc::new(Mutex::new(AccountService::new()));
+        let handles: Vec<_> = (1..=8u16)
+            .map(|client| {
+                let service = Arc::clone(&service);
+                thread::spawn(move || {
+                    for i in 0..100u32 {
+                        let transaction = Transaction {
+                            r#type: if i % 4 == 3 {
+                                TransactionType::Withdrawal
+                            } else {
+                                TransactionType::Deposit
+                            },
+                            client,
+                            tx: u32::from(client) * 1000 + i,
+                            amount: Some(Decimal::from(client)),
+                            timestamp: None,
+                        };
+                        service
+                            .lock()
+                            .unwrap()
+                            .record_transaction(transaction)
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let service = service.lock().unwrap();
+        assert_eq!(service.summary().len(), 8);
+        for client in 1..=8u16 {
+            // 75 deposits and 25 withdrawals of the client id each
+            let account = service.account(client).unwrap();
+            assert_eq!(account.available, Decimal::from(client) * Decimal::from(50));
+            assert_eq!(account.total, account.available);
+        }
+        assert_eq!(service.disputable_transactions.len(), 800);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut service = AccountService::default();
+        for (r#type, tx, amount) in [
+            (TransactionType::Deposit, 1, Some(Decimal::from(50))),
+            (TransactionType::Dispute, 1, None),
+        ] {
+            service
+                .record_transaction(Transaction {
+                    r#type,
+                    client: 1,
+                    tx,
+                    amount,
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+
+        service.reset();
+        assert!(service.summary().is_empty());
+        assert!(service.disputable_transactions.is_empty());
+        assert!(service.dispute_states.is_empty());
+        assert!(service.activity(1).is_none());
+
+        // the same tx can be deposited again in the next batch
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(5)),
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(service.account(1).unwrap().total, Decimal::from(5));
+    }
+
+    #[test]
+    fn test_unlock() {
+        let mut service = AccountService::new();
+        let transaction = |r#type, tx, amount| Transaction {
+            r#type,
+            client: 1,
+            tx,
+            amount,
+            timestamp: None,
+        };
+        assert_eq!(
+            service.record_transaction(transaction(TransactionType::Unlock, 1, None)),
+            Err(TransactionError::AccountNotLocked)
+        );
+        assert!(service.account(1).is_none());
+
+        for (r#type, tx, amount) in [
+            (TransactionType::Deposit, 1, Some(Decimal::from(50))),
+            (TransactionType::Deposit, 2, Some(Decimal::from(20))),
+            (TransactionType::Dispute, 2, None),
+            (TransactionType::Chargeback, 2, None),
+        ] {
+            service
+                .record_transaction(transaction(r#type, tx, amount))
+                .unwrap();
+        }
+        assert_eq!(
+            service.record_transaction(transaction(
+                TransactionType::Deposit,
+                3,
+                Some(Decimal::from(5))
+            )),
+            Err(TransactionError::AccountLocked)
+        );
+
+        service
+            .record_transaction(transaction(TransactionType::Unlock, 2, None))
+            .unwrap();
+        assert_eq!(
+            service.record_transaction(transaction(TransactionType::Unlock, 2, None)),
+            Err(TransactionError::AccountNotLocked)
+        );
+        service
+            .record_transaction(transaction(
+                TransactionType::Deposit,
+                3,
+                Some(Decimal::from(5)),
+            ))
+            .unwrap();
+
+        let account = service.account(1).unwrap();
+        assert!(!account.locked);
+        assert_eq!(account.available, Decimal::from(55));
+        assert_eq!(account.total, Decimal::from(55));
+        assert_eq!(service.activity(1).unwrap().locked_by, None);
+    }
+
+    #[test]
+    fn test_tx_range() {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            tx_range: Some(TxRange { min: 1, max: 100 }),
+            ..Default::default()
+        });
+        let deposit = |tx| Transaction {
+            r#type: TransactionType::Deposit,
+            client: 1,
+            tx,
+            amount: Some(Decimal::from(10)),
+            timestamp: None,
+        };
+        service.record_transaction(deposit(100)).unwrap();
+        assert_eq!(
+            service.record_transaction(deposit(101)),
+            Err(TransactionError::TxOutOfRange(TxRange { min: 1, max: 100 }))
+        );
+        assert!(service.disputable_transactions.contains_key(&(1, 100)));
+        assert!(!service.disputable_transactions.contains_key(&(1, 101)));
+        assert_eq!(service.account(1).unwrap().total, Decimal::from(10));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let deposit = |tx, amount| Transaction {
+            r#type: TransactionType::Deposit,
+            client: 1,
+            tx,
+            amount: Some(amount),
+            timestamp: None,
+        };
+        let near_max = Decimal::MAX - Decimal::from(5);
+
+        let mut service = AccountService::new();
+        service.record_transaction(deposit(1, near_max)).unwrap();
+        assert_eq!(
+            service.record_transaction(deposit(2, Decimal::from(10))),
+            Err(TransactionError::BalanceOverflow)
+        );
+        let account = service.account(1).unwrap();
+        assert_eq!(account.available, near_max);
+        assert_eq!(account.total, near_max);
+
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            overflow_mode: OverflowMode::Saturate,
+            ..Default::default()
+        });
+        service.record_transaction(deposit(1, near_max)).unwrap();
+        service
+            .record_transaction(deposit(2, Decimal::from(10)))
+            .unwrap();
+        let account = service.account(1).unwrap();
+        assert_eq!(account.available, Decimal::MAX);
+        assert_eq!(account.held, Decimal::ZERO);
+        assert_eq!(account.total, Decimal::MAX);
+    }
+
+    #[test]
+    fn test_dispute_of_spilled_transaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut service = AccountService::new();
+        service.spill_to(dir.path(), 1).unwrap();
+        for tx in 1..=3 {
+            service
+                .record_transaction(Transaction {
+                    r#type: TransactionType::Deposit,
+                    client: 1,
+                    tx,
+                    amount: Some(Decimal::from(10)),
+                    timestamp: None,
+                })
+                .unwrap();
+        }
+
+        // tx 3 didn't fit into memory anymore
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 3,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        let account = service.summary().get(&1).unwrap();
+        assert_eq!(account.available, Decimal::from(20));
+        assert_eq!(account.held, Decimal::from(10));
+
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 3,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        let account = service.summary().get(&1).unwrap();
+        assert_eq!(account.available, Decimal::from(30));
+        assert_eq!(account.held, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_verify_dispute_amounts() {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            verify_dispute_amounts: true,
+            ..Default::default()
+        });
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            })
+            .unwrap();
+
+        let res = service.record_transaction(Transaction {
             r#type: TransactionType::Resolve,
             client: 1,
             tx: 1,
-            amount: None,
+            amount: Some(Decimal::from(40)),
+            timestamp: None,
+        });
+        assert_eq!(
+            res,
+            Err(TransactionError::DisputeAmountMismatch {
+                expected: Decimal::from(50),
+                actual: Decimal::from(40),
+            })
+        );
+        assert_eq!(service.summary().get(&1).unwrap().held, Decimal::from(50));
+
+        // without an amount there's nothing to verify
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(service.summary().get(&1).unwrap().held, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_reject_dispute_amounts() {
+        let mut service = AccountService::with_options(AccountServiceOptions {
+            reject_dispute_amounts: true,
+            ..Default::default()
         });
-        service.record_transaction(Transaction {
-            r#type: TransactionType::Chargeback,
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(10)),
+                timestamp: None,
+            })
+            .unwrap();
+
+        // even the amount of the disputed transaction is rejected
+        let res = service.record_transaction(Transaction {
+            r#type: TransactionType::Dispute,
             client: 1,
             tx: 1,
-            amount: None,
+            amount: Some(Decimal::from(10)),
+            timestamp: None,
         });
+        assert_eq!(
+            res,
+            Err(TransactionError::UnexpectedAmount(Decimal::from(10)))
+        );
+        assert_eq!(service.account(1).unwrap().held, Decimal::ZERO);
 
-        let account = service.summary().get(&1);
-        assert!(account.is_some());
-
-        let account = account.unwrap();
-        assert_eq!(account.available, Decimal::ZERO);
-        assert_eq!(account.total, Decimal::ZERO);
-        assert_eq!(account.held, Decimal::ZERO);
-        assert_eq!(account.locked, true);
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        assert_eq!(service.account(1).unwrap().held, Decimal::from(10));
     }
 }
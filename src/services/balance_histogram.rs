@@ -0,0 +1,82 @@
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::data_structures::format_amount;
+
+/// Number of accounts per range of their total balance, to spot anomalies like a pile of
+/// near-zero accounts at a glance. The bounds split the balances into one more bucket than there
+/// are bounds, a balance equal to a bound falls into the bucket above it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceHistogram {
+    bounds: Vec<Decimal>,
+    counts: Vec<usize>,
+}
+
+impl BalanceHistogram {
+    pub fn new(mut bounds: Vec<Decimal>) -> Self {
+        bounds.sort_unstable();
+        bounds.dedup();
+        let counts = vec![0; bounds.len() + 1];
+        Self { bounds, counts }
+    }
+
+    pub fn observe(mut self, total: Decimal) -> Self {
+        let bucket = self.bounds.partition_point(|bound| *bound <= total);
+        self.counts[bucket] += 1;
+        self
+    }
+
+    /// Number of accounts per bucket, from the lowest to the highest balances.
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    fn label(&self, bucket: usize) -> String {
+        let lower = bucket.checked_sub(1).map(|bound| &self.bounds[bound]);
+        match (lower, self.bounds.get(bucket)) {
+            (None, Some(upper)) => format!("< {}", format_amount(upper)),
+            (Some(lower), Some(upper)) => {
+                format!("{} .. {}", format_amount(lower), format_amount(upper))
+            }
+            (Some(lower), None) => format!(">= {}", format_amount(lower)),
+            (None, None) => "all".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for BalanceHistogram {
+    /// One `range  count` line per bucket, from the lowest to the highest balances.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels = (0..self.counts.len())
+            .map(|bucket| self.label(bucket))
+            .collect::<Vec<_>>();
+        let width = labels.iter().map(String::len).max().unwrap_or_default();
+        for (label, count) in labels.iter().zip(&self.counts) {
+            writeln!(f, "{label:<width$}  {count}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        let histogram = [-5, 0, 3, 100, 250, 10_000]
+            .into_iter()
+            .map(Decimal::from)
+            .fold(
+                BalanceHistogram::new(vec![Decimal::from(1000), Decimal::ZERO, Decimal::from(100)]),
+                BalanceHistogram::observe,
+            );
+
+        assert_eq!(histogram.counts(), [1, 2, 2, 1]);
+        assert_eq!(
+            histogram.to_string(),
+            "< 0          1\n0 .. 100     2\n100 .. 1000  2\n>= 1000      1\n"
+        );
+    }
+}
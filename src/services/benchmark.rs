@@ -0,0 +1,173 @@
+use std::time::{Duration, Instant};
+
+use rust_decimal::Decimal;
+
+use crate::{
+    data_structures::{Transaction, TransactionType},
+    services::AccountService,
+};
+
+/// Generates a deterministic mix of transactions resembling a real feed: mostly deposits and
+/// withdrawals with the occasional dispute, resolve and chargeback of an earlier deposit.
+pub struct SyntheticTransactions {
+    /// State of the linear congruential generator picking the types, clients and amounts
+    state: u64,
+    remaining: usize,
+    next_tx: u32,
+    /// Deposits that can be referenced by the dispute family
+    deposits: Vec<(u16, u32)>,
+}
+
+impl SyntheticTransactions {
+    pub fn new(rows: usize, seed: u64) -> Self {
+        Self {
+            state: seed,
+            remaining: rows,
+            next_tx: 1,
+            deposits: Vec::new(),
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        // constants of Knuth's MMIX generator
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.state >> 33
+    }
+
+    fn earlier_deposit(&mut self) -> Option<(u16, u32)> {
+        if self.deposits.is_empty() {
+            return None;
+        }
+        let idx = self.next_random() as usize % self.deposits.len();
+        Some(self.deposits[idx])
+    }
+}
+
+impl Iterator for SyntheticTransactions {
+    type Item = Transaction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let roll = self.next_random() % 100;
+        let dispute_type = match roll {
+            0..=89 => None,
+            90..=95 => Some(TransactionType::Dispute),
+            96..=98 => Some(TransactionType::Resolve),
+            _ => Some(TransactionType::Chargeback),
+        };
+        if let Some(r#type) = dispute_type
+            && let Some((client, tx)) = self.earlier_deposit()
+        {
+            return Some(Transaction {
+                r#type,
+                client,
+                tx,
+                amount: None,
+                timestamp: None,
+            });
+        }
+
+        let client = (self.next_random() % 1000) as u16;
+        let tx = self.next_tx;
+        self.next_tx += 1;
+        let amount = Some(Decimal::new((self.next_random() % 1_000_000) as i64, 4));
+        let r#type = if roll < 55 {
+            self.deposits.push((client, tx));
+            TransactionType::Deposit
+        } else {
+            TransactionType::Withdrawal
+        };
+
+        Some(Transaction {
+            r#type,
+            client,
+            tx,
+            amount,
+            timestamp: None,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct BenchmarkReport {
+    pub rows: usize,
+    pub elapsed: Duration,
+    /// Peak resident memory of the process in KiB, where the platform reports it
+    pub peak_memory_kib: Option<u64>,
+}
+
+impl BenchmarkReport {
+    pub fn rows_per_sec(&self) -> f64 {
+        self.rows as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Generate the given number of synthetic transactions up front and time recording them.
+pub fn run_benchmark(rows: usize) -> BenchmarkReport {
+    let transactions = SyntheticTransactions::new(rows, 42).collect::<Vec<_>>();
+
+    let mut service = AccountService::new();
+    let start = Instant::now();
+    for transaction in transactions {
+        // rejected transactions are part of the workload just like applied ones
+        let _ = service.record_transaction(transaction);
+    }
+    let elapsed = start.elapsed();
+
+    BenchmarkReport {
+        rows,
+        elapsed,
+        peak_memory_kib: peak_memory_kib(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn peak_memory_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peak_memory_kib() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_transactions_mix() {
+        let transactions = SyntheticTransactions::new(1000, 1).collect::<Vec<_>>();
+        assert_eq!(transactions.len(), 1000);
+
+        let count = |r#type: TransactionType| {
+            transactions
+                .iter()
+                .filter(|transaction| transaction.r#type == r#type)
+                .count()
+        };
+        assert!(count(TransactionType::Deposit) > count(TransactionType::Withdrawal));
+        assert!(count(TransactionType::Withdrawal) > count(TransactionType::Dispute));
+        assert!(count(TransactionType::Dispute) > 0);
+
+        // the same seed generates the same feed
+        let again = SyntheticTransactions::new(1000, 1).collect::<Vec<_>>();
+        assert!(
+            transactions
+                .iter()
+                .zip(&again)
+                .all(|(a, b)| a.tx == b.tx && a.client == b.client && a.amount == b.amount)
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rust_decimal::Decimal;
+
+use crate::data_structures::{Transaction, TransactionType};
+
+/// Size of a spilled transaction: client, tx, type and the amount as serialized by `Decimal`.
+const RECORD_SIZE: usize = 2 + 4 + 1 + 16;
+
+/// Tells the spill files of the stores within one process apart.
+static NEXT_SPILL_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The deposits and withdrawals that can still be disputed, keyed by (client id, transaction id).
+/// Once the configured number of transactions is kept in memory, further ones are appended to a
+/// file on disk and only their offset in that file stays in memory. Lookups transparently read
+/// spilled transactions back.
+#[derive(Default)]
+pub struct DisputableStore {
+    memory: HashMap<(u16, u32), Transaction>,
+    spill: Option<SpillFile>,
+    /// Maximum number of stored transactions and the keys in insertion order, if limited
+    limit: Option<(usize, VecDeque<(u16, u32)>)>,
+}
+
+struct SpillFile {
+    path: PathBuf,
+    file: File,
+    memory_limit: usize,
+    /// Offset of the latest record of every spilled transaction
+    offsets: HashMap<(u16, u32), u64>,
+    len: u64,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl DisputableStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `memory_limit` transactions in memory and spill the rest to a file in `dir`,
+    /// which is removed again once the store is dropped.
+    pub fn spill_to(&mut self, dir: &Path, memory_limit: usize) -> io::Result<()> {
+        let path = dir.join(format!(
+            "disputable-{}-{}.bin",
+            std::process::id(),
+            NEXT_SPILL_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        self.spill = Some(SpillFile {
+            path,
+            file,
+            memory_limit,
+            offsets: HashMap::new(),
+            len: 0,
+        });
+        Ok(())
+    }
+
+    /// Keep at most `limit` transactions, evicting the oldest one once another one is inserted.
+    pub fn limit_to(&mut self, limit: usize) {
+        self.limit = Some((limit, VecDeque::new()));
+    }
+
+    pub fn len(&self) -> usize {
+        self.memory.len() + self.spill.as_ref().map_or(0, |spill| spill.offsets.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains_key(&self, key: &(u16, u32)) -> bool {
+        self.memory.contains_key(key)
+            || self
+                .spill
+                .as_ref()
+                .is_some_and(|spill| spill.offsets.contains_key(key))
+    }
+
+    pub fn get(&mut self, key: &(u16, u32)) -> io::Result<Option<Transaction>> {
+        if let Some(transaction) = self.memory.get(key) {
+            return Ok(Some(transaction.clone()));
+        }
+
+        let Some(spill) = self.spill.as_mut() else {
+            return Ok(None);
+        };
+        spill
+            .offsets
+            .get(key)
+            .copied()
+            .map(|offset| spill.read(offset))
+            .transpose()
+    }
+
+    pub fn remove(&mut self, key: &(u16, u32)) -> bool {
+        // spilled records stay in the file, only their offset is forgotten
+        self.memory.remove(key).is_some()
+            || self
+                .spill
+                .as_mut()
+                .is_some_and(|spill| spill.offsets.remove(key).is_some())
+    }
+
+    /// Remove all transactions of a client and return how many there were.
+    pub fn remove_client(&mut self, client: u16) -> usize {
+        let before = self.len();
+        self.memory
+            .retain(|(key_client, _), _| *key_client != client);
+        if let Some(spill) = self.spill.as_mut() {
+            spill
+                .offsets
+                .retain(|(key_client, _), _| *key_client != client);
+        }
+        if let Some((_, order)) = self.limit.as_mut() {
+            order.retain(|(key_client, _)| *key_client != client);
+        }
+        before - self.len()
+    }
+
+    /// Remove all transactions but keep the limit and the spill file, which is overwritten from
+    /// its start.
+    pub fn clear(&mut self) {
+        self.memory.clear();
+        if let Some(spill) = self.spill.as_mut() {
+            spill.offsets.clear();
+            spill.len = 0;
+        }
+        if let Some((_, order)) = self.limit.as_mut() {
+            order.clear();
+        }
+    }
+
+    /// Insert or replace a deposit or withdrawal. Replacing keeps the transaction wherever it's
+    /// stored at the moment. Fails only if spilling it to disk fails, in which case it isn't
+    /// stored at all.
+    pub fn insert(&mut self, key: (u16, u32), transaction: Transaction) -> io::Result<()> {
+        if !self.contains_key(&key)
+            && let Some((limit, mut order)) = self.limit.take()
+        {
+            // removed transactions may still be queued, removing them again is a no-op
+            while self.len() >= limit
+                && let Some(oldest) = order.pop_front()
+            {
+                self.remove(&oldest);
+            }
+            let has_room = limit > 0;
+            if has_room {
+                order.push_back(key);
+            }
+            self.limit = Some((limit, order));
+            if !has_room {
+                return Ok(());
+            }
+        }
+
+        let spill = match self.spill.as_mut() {
+            Some(spill)
+                if !self.memory.contains_key(&key)
+                    && (spill.offsets.contains_key(&key)
+                        || self.memory.len() >= spill.memory_limit) =>
+            {
+                spill
+            }
+            _ => {
+                self.memory.insert(key, transaction);
+                return Ok(());
+            }
+        };
+
+        let offset = spill.append(&transaction)?;
+        spill.offsets.insert(key, offset);
+        Ok(())
+    }
+}
+
+impl SpillFile {
+    fn append(&mut self, transaction: &Transaction) -> io::Result<u64> {
+        let r#type = match transaction.r#type {
+            TransactionType::Deposit => 0,
+            TransactionType::Withdrawal => 1,
+            _ => unreachable!("only deposits and withdrawals can be disputed"),
+        };
+        let mut record = Vec::with_capacity(RECORD_SIZE);
+        record.extend_from_slice(&transaction.client.to_le_bytes());
+        record.extend_from_slice(&transaction.tx.to_le_bytes());
+        record.push(r#type);
+        // disputable transactions always have an amount, a missing one is stored as zero
+        record.extend_from_slice(&transaction.amount.unwrap_or_default().serialize());
+
+        let offset = self.len;
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&record)?;
+        self.len += RECORD_SIZE as u64;
+        Ok(offset)
+    }
+
+    fn read(&mut self, offset: u64) -> io::Result<Transaction> {
+        let mut record = [0; RECORD_SIZE];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut record)?;
+
+        let r#type = match record[6] {
+            0 => TransactionType::Deposit,
+            _ => TransactionType::Withdrawal,
+        };
+        let amount = Decimal::deserialize(record[7..].try_into().expect("16 amount bytes"));
+        Ok(Transaction {
+            r#type,
+            client: u16::from_le_bytes([record[0], record[1]]),
+            tx: u32::from_le_bytes([record[2], record[3], record[4], record[5]]),
+            amount: Some(amount),
+            timestamp: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit(client: u16, tx: u32, amount: Decimal) -> Transaction {
+        Transaction {
+            r#type: TransactionType::Deposit,
+            client,
+            tx,
+            amount: Some(amount),
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_stores_spill_to_separate_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut first = DisputableStore::new();
+        let mut second = DisputableStore::new();
+        first.spill_to(dir.path(), 0).unwrap();
+        second.spill_to(dir.path(), 0).unwrap();
+        first.insert((1, 1), deposit(1, 1, Decimal::ONE)).unwrap();
+        second.insert((1, 1), deposit(1, 1, Decimal::TWO)).unwrap();
+
+        // dropping one store removes only its own file
+        drop(first);
+        assert_eq!(
+            second.get(&(1, 1)).unwrap().unwrap().amount,
+            Some(Decimal::TWO)
+        );
+        second.insert((2, 2), deposit(2, 2, Decimal::TEN)).unwrap();
+        assert_eq!(
+            second.get(&(2, 2)).unwrap().unwrap().amount,
+            Some(Decimal::TEN)
+        );
+    }
+
+    #[test]
+    fn test_spilled_transactions_are_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = DisputableStore::new();
+        store.spill_to(dir.path(), 1).unwrap();
+
+        store
+            .insert((1, 1), deposit(1, 1, Decimal::from(10)))
+            .unwrap();
+        store
+            .insert((1, 2), deposit(1, 2, Decimal::new(-12345, 4)))
+            .unwrap();
+        store
+            .insert((2, 3), deposit(2, 3, Decimal::from(30)))
+            .unwrap();
+        assert_eq!(store.memory.len(), 1);
+        assert_eq!(store.len(), 3);
+        assert!(store.contains_key(&(2, 3)));
+        assert!(!store.contains_key(&(1, 3)));
+
+        let spilled = store.get(&(1, 2)).unwrap().unwrap();
+        assert_eq!(spilled.r#type, TransactionType::Deposit);
+        assert_eq!((spilled.client, spilled.tx), (1, 2));
+        assert_eq!(spilled.amount, Some(Decimal::new(-12345, 4)));
+
+        // replacing a spilled transaction keeps it on disk
+        store
+            .insert((2, 3), deposit(2, 3, Decimal::from(35)))
+            .unwrap();
+        assert_eq!(store.memory.len(), 1);
+        assert_eq!(
+            store.get(&(2, 3)).unwrap().unwrap().amount,
+            Some(Decimal::from(35))
+        );
+    }
+
+    #[test]
+    fn test_limit_evicts_oldest() {
+        let mut store = DisputableStore::new();
+        store.limit_to(2);
+        for tx in 1..=100 {
+            store.insert((1, tx), deposit(1, tx, Decimal::ONE)).unwrap();
+            assert!(store.len() <= 2);
+        }
+        assert!(store.contains_key(&(1, 99)));
+        assert!(store.contains_key(&(1, 100)));
+
+        // replacing a stored transaction doesn't evict anything
+        store.insert((1, 99), deposit(1, 99, Decimal::TWO)).unwrap();
+        assert!(store.contains_key(&(1, 100)));
+    }
+
+    #[test]
+    fn test_remove_client() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = DisputableStore::new();
+        store.spill_to(dir.path(), 1).unwrap();
+        store.insert((1, 1), deposit(1, 1, Decimal::ONE)).unwrap();
+        store.insert((2, 2), deposit(2, 2, Decimal::ONE)).unwrap();
+        store.insert((1, 3), deposit(1, 3, Decimal::ONE)).unwrap();
+
+        assert_eq!(store.remove_client(1), 2);
+        assert_eq!(store.len(), 1);
+        assert!(store.contains_key(&(2, 2)));
+    }
+
+    #[test]
+    fn test_remove_client_with_limit() {
+        let mut store = DisputableStore::new();
+        store.limit_to(2);
+        store.insert((1, 1), deposit(1, 1, Decimal::ONE)).unwrap();
+        store.insert((2, 1), deposit(2, 1, Decimal::ONE)).unwrap();
+
+        assert_eq!(store.remove_client(1), 1);
+        assert_eq!(store.limit.as_ref().unwrap().1.len(), 1);
+
+        // the removed key isn't queued anymore, so inserting it again doesn't get it evicted early
+        store.insert((1, 1), deposit(1, 1, Decimal::TWO)).unwrap();
+        store.insert((2, 2), deposit(2, 2, Decimal::ONE)).unwrap();
+        assert_eq!(store.limit.as_ref().unwrap().1.len(), 2);
+        assert!(!store.contains_key(&(2, 1)));
+        assert_eq!(
+            store.get(&(1, 1)).unwrap().unwrap().amount,
+            Some(Decimal::TWO)
+        );
+        assert!(store.contains_key(&(2, 2)));
+    }
+
+    #[test]
+    fn test_spill_file_is_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = DisputableStore::new();
+        store.spill_to(dir.path(), 0).unwrap();
+        store.insert((1, 1), deposit(1, 1, Decimal::ONE)).unwrap();
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        drop(store);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
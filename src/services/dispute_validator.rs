@@ -0,0 +1,211 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    data_structures::{Transaction, TransactionType},
+    readers::RowNumber,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DisputeViolation {
+    pub row_number: RowNumber,
+    pub r#type: TransactionType,
+    pub tx: u32,
+}
+
+impl fmt::Display for DisputeViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected = match self.r#type {
+            TransactionType::Dispute => "deposit or withdrawal",
+            _ => "dispute",
+        };
+        write!(
+            f,
+            "{}: {:?} for tx {} has no prior {expected}",
+            self.row_number, self.r#type, self.tx
+        )
+    }
+}
+
+/// Collects dispute-family events that reference something which hasn't been seen yet in the
+/// file, e.g. a resolve that arrives before its dispute. This only looks at the order of the
+/// rows, it doesn't care whether the referenced transaction was actually applied. Like the
+/// accounts, transactions are keyed by (client id, transaction id), so a client can't dispute the
+/// transaction of another one.
+#[derive(Default)]
+pub struct DisputeValidator {
+    seen_transactions: HashSet<(u16, u32)>,
+    disputed_transactions: HashSet<(u16, u32)>,
+    pub violations: Vec<DisputeViolation>,
+}
+
+impl DisputeValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, row_number: &RowNumber, transaction: &Transaction) {
+        let key = (transaction.client, transaction.tx);
+        let is_dangling = match transaction.r#type {
+            TransactionType::Deposit | TransactionType::Withdrawal => {
+                self.seen_transactions.insert(key);
+                false
+            }
+            TransactionType::Dispute => {
+                // a dangling dispute doesn't open anything a later resolve could refer to
+                let is_seen = self.seen_transactions.contains(&key);
+                if is_seen {
+                    self.disputed_transactions.insert(key);
+                }
+                !is_seen
+            }
+            TransactionType::Resolve | TransactionType::Chargeback => {
+                !self.disputed_transactions.contains(&key)
+            }
+            TransactionType::Unlock | TransactionType::Unknown(_) => false,
+        };
+
+        if is_dangling {
+            self.violations.push(DisputeViolation {
+                row_number: row_number.clone(),
+                r#type: transaction.r#type.clone(),
+                tx: transaction.tx,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve_before_dispute_is_reported() {
+        let mut validator = DisputeValidator::new();
+        validator.observe(
+            &RowNumber::new(1),
+            &Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(50)),
+                timestamp: None,
+            },
+        );
+        validator.observe(
+            &RowNumber::new(2),
+            &Transaction {
+                r#type: TransactionType::Resolve,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            },
+        );
+        validator.observe(
+            &RowNumber::new(3),
+            &Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 1,
+                amount: None,
+                timestamp: None,
+            },
+        );
+
+        assert_eq!(
+            validator.violations,
+            vec![DisputeViolation {
+                row_number: RowNumber::new(2),
+                r#type: TransactionType::Resolve,
+                tx: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_dispute_of_other_clients_transaction_is_reported() {
+        let mut validator = DisputeValidator::new();
+        let rows = [
+            (TransactionType::Deposit, 1, Some(Decimal::TEN)),
+            (TransactionType::Dispute, 2, None),
+            (TransactionType::Resolve, 2, None),
+        ];
+        for (row_number, (r#type, client, amount)) in rows.into_iter().enumerate() {
+            validator.observe(
+                &RowNumber::new(row_number + 1),
+                &Transaction {
+                    r#type,
+                    client,
+                    tx: 7,
+                    amount,
+                    timestamp: None,
+                },
+            );
+        }
+
+        assert_eq!(
+            validator.violations,
+            vec![
+                DisputeViolation {
+                    row_number: RowNumber::new(2),
+                    r#type: TransactionType::Dispute,
+                    tx: 7,
+                },
+                DisputeViolation {
+                    row_number: RowNumber::new(3),
+                    r#type: TransactionType::Resolve,
+                    tx: 7,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_of_dangling_dispute_is_reported() {
+        let mut validator = DisputeValidator::new();
+        for (row_number, r#type) in [TransactionType::Dispute, TransactionType::Chargeback]
+            .into_iter()
+            .enumerate()
+        {
+            validator.observe(
+                &RowNumber::new(row_number + 1),
+                &Transaction {
+                    r#type,
+                    client: 1,
+                    tx: 7,
+                    amount: None,
+                    timestamp: None,
+                },
+            );
+        }
+
+        assert_eq!(validator.violations.len(), 2);
+        assert_eq!(
+            validator.violations[1].to_string(),
+            "row 2: Chargeback for tx 7 has no prior dispute"
+        );
+    }
+
+    #[test]
+    fn test_dispute_without_transaction_is_reported() {
+        let mut validator = DisputeValidator::new();
+        validator.observe(
+            &RowNumber::new(1),
+            &Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 7,
+                amount: None,
+                timestamp: None,
+            },
+        );
+
+        assert_eq!(validator.violations.len(), 1);
+        assert_eq!(
+            validator.violations[0].to_string(),
+            "row 1: Dispute for tx 7 has no prior deposit or withdrawal"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    data_structures::{Transaction, TransactionType},
+    readers::RowNumber,
+};
+
+/// A transaction id that is used by more than one deposit or withdrawal.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DuplicateTx {
+    pub tx: u32,
+    pub row_numbers: Vec<RowNumber>,
+}
+
+impl fmt::Display for DuplicateTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self
+            .row_numbers
+            .iter()
+            .map(|row_number| match row_number.file_name() {
+                Some(file) => format!("{} of '{file}'", row_number.row),
+                None => row_number.row.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "tx {} appears in rows {rows}", self.tx)
+    }
+}
+
+/// Collects the rows of every deposit and withdrawal by transaction id. Disputes reference a
+/// transaction by its id, so an id that's reused makes it ambiguous which one is disputed.
+#[derive(Default)]
+pub struct DuplicateTxDetector {
+    row_numbers: BTreeMap<u32, Vec<RowNumber>>,
+}
+
+impl DuplicateTxDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, row_number: &RowNumber, transaction: &Transaction) {
+        if matches!(
+            transaction.r#type,
+            TransactionType::Deposit | TransactionType::Withdrawal
+        ) {
+            self.row_numbers
+                .entry(transaction.tx)
+                .or_default()
+                .push(row_number.clone());
+        }
+    }
+
+    /// The transaction ids that appeared more than once, in ascending order.
+    pub fn duplicates(&self) -> Vec<DuplicateTx> {
+        self.row_numbers
+            .iter()
+            .filter(|(_, row_numbers)| row_numbers.len() > 1)
+            .map(|(tx, row_numbers)| DuplicateTx {
+                tx: *tx,
+                row_numbers: row_numbers.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    #[test]
+    fn test_duplicate_tx_is_reported() {
+        let mut detector = DuplicateTxDetector::new();
+        let rows = [
+            (TransactionType::Deposit, 1, 1),
+            (TransactionType::Deposit, 2, 2),
+            (TransactionType::Withdrawal, 1, 1),
+            (TransactionType::Dispute, 1, 2),
+            (TransactionType::Deposit, 3, 3),
+        ];
+        for (row_number, (r#type, client, tx)) in rows.into_iter().enumerate() {
+            detector.observe(
+                &RowNumber::new(row_number + 1),
+                &Transaction {
+                    r#type,
+                    client,
+                    tx,
+                    amount: Some(Decimal::ONE),
+                    timestamp: None,
+                },
+            );
+        }
+
+        let duplicates = detector.duplicates();
+        assert_eq!(
+            duplicates,
+            vec![DuplicateTx {
+                tx: 1,
+                row_numbers: vec![RowNumber::new(1), RowNumber::new(3)],
+            }]
+        );
+        assert_eq!(duplicates[0].to_string(), "tx 1 appears in rows 1, 3");
+    }
+}
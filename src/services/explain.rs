@@ -0,0 +1,94 @@
+use crate::{data_structures::format_amount, services::AccountService};
+
+/// A human readable narrative of what happened to the account of a client during the run.
+pub fn explain_account(service: &AccountService, client: u16) -> String {
+    let (Some(account), Some(activity)) =
+        (service.summary().get(&client), service.activity(client))
+    else {
+        return format!("client {client} has no applied transactions");
+    };
+
+    let mut narrative = format!(
+        "client {client} deposited {} and withdrew {} in total, {} dispute(s) were opened and {} is currently held.",
+        format_amount(&activity.deposited),
+        format_amount(&activity.withdrawn),
+        activity.disputes,
+        format_amount(&account.held),
+    );
+    match activity.locked_by {
+        Some(tx) if account.locked => narrative.push_str(&format!(
+            " The account is locked because tx {tx} was charged back."
+        )),
+        _ => narrative.push_str(" The account is not locked."),
+    }
+
+    narrative
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::data_structures::{Transaction, TransactionType};
+
+    #[test]
+    fn test_explain_locked_account() {
+        let mut service = AccountService::new();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 1,
+                amount: Some(Decimal::from(100)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Withdrawal,
+                client: 1,
+                tx: 2,
+                amount: Some(Decimal::new(305, 1)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Deposit,
+                client: 1,
+                tx: 3,
+                amount: Some(Decimal::from(20)),
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Dispute,
+                client: 1,
+                tx: 3,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+        service
+            .record_transaction(Transaction {
+                r#type: TransactionType::Chargeback,
+                client: 1,
+                tx: 3,
+                amount: None,
+                timestamp: None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            explain_account(&service, 1),
+            "client 1 deposited 120 and withdrew 30.5 in total, 1 dispute(s) were opened and 0 is currently held. \
+             The account is locked because tx 3 was charged back."
+        );
+        assert_eq!(
+            explain_account(&service, 2),
+            "client 2 has no applied transactions"
+        );
+    }
+}
@@ -1,2 +1,24 @@
 mod account;
+mod balance_histogram;
+mod benchmark;
+mod disputable_store;
+mod dispute_validator;
+mod duplicate_tx;
+mod explain;
+mod rejections;
+mod reversal_error;
+mod stats;
+mod transaction_error;
+mod tx_range;
 pub use account::*;
+pub use balance_histogram::*;
+pub use benchmark::*;
+pub use disputable_store::*;
+pub use dispute_validator::*;
+pub use duplicate_tx::*;
+pub use explain::*;
+pub use rejections::*;
+pub use reversal_error::*;
+pub use stats::*;
+pub use transaction_error::*;
+pub use tx_range::*;
@@ -0,0 +1,52 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::services::TransactionError;
+
+/// Reason given for rows that couldn't be parsed into a transaction at all.
+pub const PARSE_ERROR_REASON: &str = "parse_error";
+
+/// Counts the rows that weren't applied by the reason they were rejected for, to get an overview
+/// of the quality of a file without going through every single error.
+#[derive(Default)]
+pub struct RejectionCounts {
+    counts: BTreeMap<&'static str, usize>,
+}
+
+impl RejectionCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, err: &TransactionError) {
+        self.observe_reason(err.reason());
+    }
+
+    pub fn observe_parse_error(&mut self) {
+        self.observe_reason(PARSE_ERROR_REASON);
+    }
+
+    fn observe_reason(&mut self, reason: &'static str) {
+        *self.counts.entry(reason).or_default() += 1;
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+}
+
+impl fmt::Display for RejectionCounts {
+    /// One `reason: count` line per reason, the most frequent reason first.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut counts = self.counts.iter().collect::<Vec<_>>();
+        counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let width = counts
+            .iter()
+            .map(|(reason, _)| reason.len())
+            .max()
+            .unwrap_or_default();
+        for (reason, count) in counts {
+            writeln!(f, "{reason:<width$}  {count}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt;
+
+/// Why a transaction couldn't be reversed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReversalError {
+    /// No deposit or withdrawal with this id was applied
+    UnknownTransaction(u32),
+    /// The account of the transaction was locked by a chargeback
+    AccountLocked {
+        client: u16,
+    },
+    AlreadyReversed(u32),
+    /// The transaction is under an open dispute which has to be settled first
+    UnderDispute(u32),
+    /// The transaction couldn't be read back from the spill file
+    SpillFailed(String),
+    /// Undoing the transaction would overflow a balance of the client
+    BalanceOverflow {
+        client: u16,
+    },
+}
+
+impl fmt::Display for ReversalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownTransaction(tx) => write!(f, "no deposit or withdrawal with tx {tx}"),
+            Self::AccountLocked { client } => write!(f, "the account of client {client} is locked"),
+            Self::AlreadyReversed(tx) => write!(f, "tx {tx} was already reversed"),
+            Self::UnderDispute(tx) => write!(f, "tx {tx} is under an open dispute"),
+            Self::SpillFailed(err) => write!(f, "failed to read the spilled transactions: {err}"),
+            Self::BalanceOverflow { client } => {
+                write!(f, "the balances of client {client} would overflow")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReversalError {}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use crate::{data_structures::TransactionType, services::TransactionError};
+
+/// Counts of what happened to the rows of a run, for auditing a batch as a whole.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProcessingStats {
+    pub rows: usize,
+    pub deposits: usize,
+    pub withdrawals: usize,
+    pub disputes: usize,
+    pub resolves: usize,
+    pub chargebacks: usize,
+    pub unlocks: usize,
+    pub parse_errors: usize,
+    /// Transactions that were parsed but not applied
+    pub skipped: usize,
+}
+
+impl ProcessingStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe_parse_error(&mut self) {
+        self.rows += 1;
+        self.parse_errors += 1;
+    }
+
+    pub fn observe(&mut self, r#type: &TransactionType, res: &Result<(), TransactionError>) {
+        self.rows += 1;
+        if res.is_err() {
+            self.skipped += 1;
+            return;
+        }
+        match r#type {
+            TransactionType::Deposit => self.deposits += 1,
+            TransactionType::Withdrawal => self.withdrawals += 1,
+            TransactionType::Dispute => self.disputes += 1,
+            TransactionType::Resolve => self.resolves += 1,
+            TransactionType::Chargeback => self.chargebacks += 1,
+            TransactionType::Unlock => self.unlocks += 1,
+            // never applied, so already counted as skipped
+            TransactionType::Unknown(_) => {}
+        }
+    }
+}
+
+impl fmt::Display for ProcessingStats {
+    /// One `name  count` line per count, the applied transactions by type in between the total
+    /// and the rows that weren't applied.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = [
+            ("rows", self.rows),
+            ("deposits", self.deposits),
+            ("withdrawals", self.withdrawals),
+            ("disputes", self.disputes),
+            ("resolves", self.resolves),
+            ("chargebacks", self.chargebacks),
+            ("unlocks", self.unlocks),
+            ("parse errors", self.parse_errors),
+            ("skipped", self.skipped),
+        ];
+        let width = counts
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or_default();
+        for (name, count) in counts {
+            writeln!(f, "{name:<width$}  {count}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut stats = ProcessingStats::new();
+        stats.observe(&TransactionType::Deposit, &Ok(()));
+        stats.observe(&TransactionType::Deposit, &Ok(()));
+        stats.observe(
+            &TransactionType::Withdrawal,
+            &Err(TransactionError::InsufficientFunds),
+        );
+        stats.observe(&TransactionType::Dispute, &Ok(()));
+        stats.observe_parse_error();
+
+        assert_eq!(
+            stats,
+            ProcessingStats {
+                rows: 5,
+                deposits: 2,
+                disputes: 1,
+                parse_errors: 1,
+                skipped: 1,
+                ..Default::default()
+            }
+        );
+        assert!(
+            stats
+                .to_string()
+                .starts_with("rows          5\ndeposits      2\n")
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::{fmt, io};
+
+use rust_decimal::Decimal;
+
+use crate::{
+    data_structures::{DisputeState, TransactionType, format_amount},
+    services::TxRange,
+};
+
+/// Why a transaction wasn't applied to its account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    /// A deposit or withdrawal without an amount
+    MissingAmount,
+    /// A withdrawal of more than the available funds
+    InsufficientFunds,
+    /// The account was locked by a chargeback and doesn't accept transactions anymore
+    AccountLocked,
+    /// A dispute, resolve or chargeback of a transaction the client doesn't have
+    UnknownDisputeTarget,
+    /// A resolve by transaction id alone while these clients have an open dispute with that id
+    AmbiguousDisputeTarget(Vec<u16>),
+    /// E.g. a resolve of a transaction that isn't under dispute
+    InvalidDisputeTransition {
+        state: DisputeState,
+        r#type: TransactionType,
+    },
+    /// A dispute, resolve or chargeback whose amount differs from the disputed transaction
+    DisputeAmountMismatch {
+        expected: Decimal,
+        actual: Decimal,
+    },
+    /// A dispute, resolve or chargeback with an amount while they aren't allowed to have one
+    UnexpectedAmount(Decimal),
+    /// A deposit or withdrawal reusing the id of an earlier applied transaction of the client
+    DuplicateTransactionId,
+    /// A dispute of a transaction that was never disputed while the maximum number of tracked
+    /// disputes is reached
+    DisputeLimitReached {
+        limit: usize,
+    },
+    /// A deposit or withdrawal with an amount that isn't a multiple of the minimum unit
+    SubUnitAmount {
+        amount: Decimal,
+        min_unit: Decimal,
+    },
+    /// A transaction that would take a balance beyond the representable range
+    BalanceOverflow,
+    /// An unlock of an account that isn't locked
+    AccountNotLocked,
+    /// A transaction whose id is outside of the declared range of ids
+    TxOutOfRange(TxRange),
+    /// Reading or writing the spilled disputable transactions failed, which says nothing about the
+    /// transaction itself
+    SpillFailed(String),
+    /// A split deposit part for a transaction that isn't an undisputed deposit
+    SplitDepositRejected,
+    UnknownType(String),
+}
+
+impl TransactionError {
+    pub fn spill_failed(err: io::Error) -> Self {
+        Self::SpillFailed(err.to_string())
+    }
+
+    /// Stable, machine readable identifier of the error
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::MissingAmount => "missing_amount",
+            Self::InsufficientFunds => "insufficient_funds",
+            Self::AccountLocked => "account_locked",
+            Self::UnknownDisputeTarget => "unknown_dispute_target",
+            Self::AmbiguousDisputeTarget(_) => "ambiguous_dispute_target",
+            Self::InvalidDisputeTransition { .. } => "invalid_dispute_transition",
+            Self::DisputeAmountMismatch { .. } => "dispute_amount_mismatch",
+            Self::UnexpectedAmount(_) => "unexpected_amount",
+            Self::DuplicateTransactionId => "duplicate_transaction_id",
+            Self::DisputeLimitReached { .. } => "dispute_limit_reached",
+            Self::SubUnitAmount { .. } => "sub_unit_amount",
+            Self::BalanceOverflow => "balance_overflow",
+            Self::AccountNotLocked => "account_not_locked",
+            Self::TxOutOfRange(_) => "tx_out_of_range",
+            Self::SpillFailed(_) => "spill_failed",
+            Self::SplitDepositRejected => "split_deposit_rejected",
+            Self::UnknownType(_) => "unknown_type",
+        }
+    }
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingAmount => write!(f, "the transaction has no amount"),
+            Self::InsufficientFunds => write!(f, "the available funds don't cover the withdrawal"),
+            Self::AccountLocked => write!(f, "the account is locked"),
+            Self::UnknownDisputeTarget => {
+                write!(f, "the client has no deposit or withdrawal with this id")
+            }
+            Self::AmbiguousDisputeTarget(clients) => write!(
+                f,
+                "clients {} all have an open dispute with this id",
+                clients
+                    .iter()
+                    .map(u16::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::InvalidDisputeTransition { state, r#type } => {
+                write!(
+                    f,
+                    "a {type:?} isn't valid for a transaction in dispute state {state:?}"
+                )
+            }
+            Self::DisputeAmountMismatch { expected, actual } => write!(
+                f,
+                "the amount {} doesn't match the disputed amount {}",
+                format_amount(actual),
+                format_amount(expected)
+            ),
+            Self::UnexpectedAmount(amount) => write!(
+                f,
+                "disputes, resolves and chargebacks have no amount but got {}",
+                format_amount(amount)
+            ),
+            Self::DuplicateTransactionId => {
+                write!(f, "the client already has a transaction with this id")
+            }
+            Self::DisputeLimitReached { limit } => {
+                write!(f, "already tracking the maximum of {limit} disputes")
+            }
+            Self::SubUnitAmount { amount, min_unit } => write!(
+                f,
+                "the amount {} isn't a multiple of the minimum unit {}",
+                format_amount(amount),
+                format_amount(min_unit)
+            ),
+            Self::BalanceOverflow => write!(f, "a balance would overflow"),
+            Self::AccountNotLocked => write!(f, "the account isn't locked"),
+            Self::TxOutOfRange(range) => write!(f, "the id is outside of the range {range}"),
+            Self::SpillFailed(err) => {
+                write!(f, "failed to access the spilled transactions: {err}")
+            }
+            Self::SplitDepositRejected => write!(
+                f,
+                "only undisputed deposits can be summed with a deposit of the same id"
+            ),
+            Self::UnknownType(token) => write!(f, "unknown transaction type '{token}'"),
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
@@ -0,0 +1,67 @@
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+/// Inclusive range `MIN:MAX` the transaction ids of a partner are declared to lie in. An id
+/// outside of it hints at corrupted input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl TxRange {
+    pub fn contains(&self, tx: u32) -> bool {
+        RangeInclusive::new(self.min, self.max).contains(&tx)
+    }
+}
+
+impl FromStr for TxRange {
+    type Err = String;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let (min, max) = range
+            .split_once(':')
+            .ok_or_else(|| format!("expected `MIN:MAX` but got '{range}'"))?;
+        let min = min
+            .parse::<u32>()
+            .map_err(|err| format!("invalid range min: {err}"))?;
+        let max = max
+            .parse::<u32>()
+            .map_err(|err| format!("invalid range max: {err}"))?;
+        if min > max {
+            return Err(format!("range min {min} is above its max {max}"));
+        }
+
+        Ok(Self { min, max })
+    }
+}
+
+impl fmt::Display for TxRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.min, self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let range: TxRange = "1:1000000".parse().unwrap();
+        assert_eq!(
+            range,
+            TxRange {
+                min: 1,
+                max: 1_000_000
+            }
+        );
+        assert!(range.contains(1));
+        assert!(range.contains(1_000_000));
+        assert!(!range.contains(0));
+        assert!(!range.contains(1_000_001));
+
+        assert!("5".parse::<TxRange>().is_err());
+        assert!("5:x".parse::<TxRange>().is_err());
+        assert!("5:4".parse::<TxRange>().is_err());
+    }
+}
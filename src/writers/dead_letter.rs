@@ -0,0 +1,84 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::{
+    data_structures::{Transaction, TransactionType, format_amount},
+    readers::RowNumber,
+    services::{PARSE_ERROR_REASON, TransactionError},
+};
+
+/// A row that didn't make it into the accounts. The transaction columns are empty for rows that
+/// couldn't be parsed, the file is only named when several transaction files are read.
+#[derive(Debug, Serialize)]
+struct DeadLetter<'a> {
+    row: usize,
+    file: Option<String>,
+    r#type: Option<&'a TransactionType>,
+    client: Option<u16>,
+    tx: Option<u32>,
+    amount: Option<String>,
+    /// Machine readable identifier, e.g. `insufficient_funds`
+    reason: &'static str,
+    detail: String,
+}
+
+/// Collects every row that couldn't be parsed or applied in a csv file that can be handed back to
+/// whoever produced the input.
+pub struct DeadLetterWriter {
+    writer: csv::Writer<BufWriter<File>>,
+}
+
+impl DeadLetterWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create dead-letter file '{}'", path.display()))?;
+        Ok(Self {
+            writer: csv::Writer::from_writer(BufWriter::new(file)),
+        })
+    }
+
+    pub fn write_parse_error(&mut self, row_number: &RowNumber, err: &anyhow::Error) -> Result<()> {
+        self.write(DeadLetter {
+            row: row_number.row,
+            file: row_number.file_name(),
+            r#type: None,
+            client: None,
+            tx: None,
+            amount: None,
+            reason: PARSE_ERROR_REASON,
+            detail: format!("{err:#}"),
+        })
+    }
+
+    pub fn write_rejected(
+        &mut self,
+        row_number: &RowNumber,
+        transaction: &Transaction,
+        err: &TransactionError,
+    ) -> Result<()> {
+        self.write(DeadLetter {
+            row: row_number.row,
+            file: row_number.file_name(),
+            r#type: Some(&transaction.r#type),
+            client: Some(transaction.client),
+            tx: Some(transaction.tx),
+            amount: transaction.amount.as_ref().map(format_amount),
+            reason: err.reason(),
+            detail: err.to_string(),
+        })
+    }
+
+    fn write(&mut self, dead_letter: DeadLetter) -> Result<()> {
+        self.writer
+            .serialize(dead_letter)
+            .context("failed to write to the dead-letter file")
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer
+            .flush()
+            .context("failed to flush the dead-letter file")
+    }
+}
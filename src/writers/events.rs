@@ -0,0 +1,119 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::data_structures::{Account, Transaction, TransactionType, format_amount};
+
+/// The balances of an account at one point in time, to tell how a transaction changed them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Balances {
+    pub available: Decimal,
+    pub held: Decimal,
+    pub total: Decimal,
+}
+
+impl Balances {
+    pub fn of(account: &Account) -> Self {
+        Self {
+            available: account.available,
+            held: account.held,
+            total: account.total,
+        }
+    }
+}
+
+/// How an applied transaction changed the balances of its account.
+#[derive(Debug, Serialize)]
+struct BalanceEvent<'a> {
+    client: u16,
+    tx: u32,
+    r#type: &'a TransactionType,
+    delta_available: String,
+    delta_held: String,
+    delta_total: String,
+}
+
+/// Writes a JSON object per balance change to a file, for consumers that follow the accounts
+/// incrementally instead of waiting for the summary.
+pub struct EventWriter {
+    writer: BufWriter<File>,
+}
+
+impl EventWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create events file '{}'", path.display()))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Write the event of an applied transaction, unless it left the balances as they were.
+    pub fn write(
+        &mut self,
+        transaction: &Transaction,
+        before: Balances,
+        after: Balances,
+    ) -> Result<()> {
+        if before == after {
+            return Ok(());
+        }
+        write_event(
+            &mut self.writer,
+            &BalanceEvent {
+                client: transaction.client,
+                tx: transaction.tx,
+                r#type: &transaction.r#type,
+                delta_available: format_amount(&(after.available - before.available)),
+                delta_held: format_amount(&(after.held - before.held)),
+                delta_total: format_amount(&(after.total - before.total)),
+            },
+        )
+        .context("failed to write to the events file")
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer
+            .flush()
+            .context("failed to flush the events file")
+    }
+}
+
+fn write_event(writer: &mut impl Write, event: &BalanceEvent) -> Result<()> {
+    serde_json::to_writer(&mut *writer, event)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_json_line() {
+        let mut output = Vec::new();
+        write_event(
+            &mut output,
+            &BalanceEvent {
+                client: 1,
+                tx: 2,
+                r#type: &TransactionType::Dispute,
+                delta_available: format_amount(&Decimal::new(-15, 1)),
+                delta_held: format_amount(&Decimal::new(15, 1)),
+                delta_total: format_amount(&Decimal::ZERO),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"client\":1,\"tx\":2,\"type\":\"dispute\",\"delta_available\":\"-1.5\",\"delta_held\":\"1.5\",\"delta_total\":\"0\"}\n"
+        );
+    }
+}
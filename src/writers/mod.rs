@@ -0,0 +1,93 @@
+mod dead_letter;
+mod events;
+mod pipe;
+mod round_trip;
+mod summary;
+mod summary_hash;
+mod warnings;
+pub use dead_letter::*;
+pub use events::*;
+pub use pipe::*;
+pub use round_trip::*;
+pub use summary::*;
+pub use summary_hash::*;
+pub use warnings::*;
+
+use std::io::{self, BufWriter, Write};
+
+use flate2::write::GzEncoder;
+
+/// A writer that may have to write trailing data once everything else was written, like the
+/// footer of a gzip stream. Just flushing such a writer leaves a truncated output behind.
+pub trait FinishWrite: Write {
+    fn finish(self) -> io::Result<()>;
+}
+
+impl<W: Write> FinishWrite for BufWriter<W> {
+    fn finish(mut self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+impl<W: Write> FinishWrite for &mut W {
+    fn finish(self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+impl<W: Write> FinishWrite for GzEncoder<W> {
+    fn finish(self) -> io::Result<()> {
+        GzEncoder::finish(self)?.flush()
+    }
+}
+
+/// Whether the given file descriptor is a named pipe. A pipe reader usually consumes the data
+/// as it arrives, so output written to it should be flushed incrementally instead of only once at
+/// the end.
+#[cfg(unix)]
+pub fn is_fifo(fd: impl std::os::fd::AsFd) -> bool {
+    use std::{fs::File, os::unix::fs::FileTypeExt};
+
+    fd.as_fd()
+        .try_clone_to_owned()
+        .and_then(|fd| File::from(fd).metadata())
+        .map(|metadata| metadata.file_type().is_fifo())
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo<T>(_fd: T) -> bool {
+    false
+}
+
+/// Whether the path is a named pipe. Unlike opening it, this doesn't block until the other end is
+/// connected.
+#[cfg(unix)]
+pub fn is_fifo_path(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo_path(_path: &std::path::Path) -> bool {
+    false
+}
+
+/// Whether the error was caused by the reader of the output going away, e.g. a fifo reader that
+/// disconnected or `head` closing the pipe.
+pub fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        let io_err = match cause.downcast_ref::<csv::Error>() {
+            Some(csv_err) => match csv_err.kind() {
+                csv::ErrorKind::Io(io_err) => io_err,
+                _ => return false,
+            },
+            None => match cause.downcast_ref::<io::Error>() {
+                Some(io_err) => io_err,
+                None => return false,
+            },
+        };
+        io_err.kind() == io::ErrorKind::BrokenPipe
+    })
+}
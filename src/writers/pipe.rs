@@ -0,0 +1,71 @@
+use std::process::{Child, ChildStdin, Command, ExitStatus, Stdio};
+
+use anyhow::{Context, Result};
+
+use crate::{data_structures::Transaction, writers::is_broken_pipe};
+
+pub struct PipeStatus {
+    pub exit_status: ExitStatus,
+    /// Whether the command stopped reading before all transactions were forwarded
+    pub closed_early: bool,
+}
+
+/// Forwards transactions as csv to the stdin of a shell command. The command may stop reading at
+/// any time, e.g. `head`, after which the transactions are dropped instead of failing the run.
+pub struct TransactionPipe {
+    child: Child,
+    writer: Option<csv::Writer<ChildStdin>>,
+}
+
+impl TransactionPipe {
+    pub fn spawn(command: &str) -> Result<Self> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to spawn '{command}'"))?;
+        let stdin = child.stdin.take().expect("stdin to be piped");
+
+        Ok(Self {
+            child,
+            writer: Some(csv::Writer::from_writer(stdin)),
+        })
+    }
+
+    pub fn forward(&mut self, transaction: &Transaction) -> Result<()> {
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+        match writer.serialize(transaction).map_err(anyhow::Error::from) {
+            Err(err) if is_broken_pipe(&err) => {
+                self.writer = None;
+                Ok(())
+            }
+            res => res.context("failed to forward a transaction"),
+        }
+    }
+
+    /// Close the stdin of the command and wait for it to exit.
+    pub fn finish(mut self) -> Result<PipeStatus> {
+        let mut closed_early = true;
+        if let Some(mut writer) = self.writer.take() {
+            match writer.flush().map_err(anyhow::Error::from) {
+                Err(err) if is_broken_pipe(&err) => {}
+                res => {
+                    res.context("failed to forward transactions")?;
+                    closed_early = false;
+                }
+            }
+        }
+        let exit_status = self
+            .child
+            .wait()
+            .context("failed to wait for the pipe command")?;
+
+        Ok(PipeStatus {
+            exit_status,
+            closed_early,
+        })
+    }
+}
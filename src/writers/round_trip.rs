@@ -0,0 +1,232 @@
+use std::{collections::HashMap, fs::File, io::Read, path::Path};
+
+use anyhow::{Context, Result, bail};
+use flate2::read::GzDecoder;
+
+use crate::{
+    data_structures::Account,
+    writers::{LineTerminator, OutputFormat},
+};
+
+/// Read back a summary that was written to a file, decompressing it if it's a `.gz` file.
+pub fn read_written_summary(path: &Path) -> Result<Vec<u8>> {
+    let file = File::open(path)
+        .with_context(|| format!("round-trip check failed to open '{}'", path.display()))?;
+    let mut written = Vec::new();
+    let read_res = if path.extension().is_some_and(|extension| extension == "gz") {
+        GzDecoder::new(file).read_to_end(&mut written)
+    } else {
+        (&file).read_to_end(&mut written)
+    };
+    read_res.with_context(|| format!("round-trip check failed to read '{}'", path.display()))?;
+    Ok(written)
+}
+
+/// Parse a written summary and make sure it holds exactly the given accounts in the given order,
+/// which catches amounts that lose precision or change when they're serialized. A csv summary
+/// must also end its lines with the given terminator.
+pub fn verify_round_trip<'a>(
+    accounts: impl IntoIterator<Item = &'a Account>,
+    written: &[u8],
+    format: OutputFormat,
+    line_terminator: LineTerminator,
+) -> Result<()> {
+    let written_accounts = match format {
+        OutputFormat::Csv => {
+            let lines = written.iter().filter(|byte| **byte == b'\n').count();
+            let crlf_lines = written.windows(2).filter(|pair| pair == b"\r\n").count();
+            let expected_crlf_lines = match line_terminator {
+                LineTerminator::Lf => 0,
+                LineTerminator::Crlf => lines,
+            };
+            if crlf_lines != expected_crlf_lines {
+                bail!(
+                    "round-trip check failed: the summary doesn't end its lines with {line_terminator:?}"
+                );
+            }
+            csv::Reader::from_reader(written)
+                .into_deserialize::<Account>()
+                .collect::<Result<Vec<_>, _>>()
+                .context("round-trip check failed to parse the summary")?
+        }
+        OutputFormat::Json => serde_json::from_slice::<Vec<Account>>(written)
+            .context("round-trip check failed to parse the summary")?,
+    };
+
+    let mut written_clients = HashMap::new();
+    for (position, account) in written_accounts.iter().enumerate() {
+        if written_clients.insert(account.client, position).is_some() {
+            bail!(
+                "round-trip check failed: client {} was written twice",
+                account.client
+            );
+        }
+    }
+
+    let mut expected_accounts = 0;
+    for (position, account) in accounts.into_iter().enumerate() {
+        expected_accounts += 1;
+        let Some(&written_position) = written_clients.get(&account.client) else {
+            bail!(
+                "round-trip check failed: client {} is missing from the summary",
+                account.client
+            );
+        };
+        let written_account = &written_accounts[written_position];
+        if written_account != account {
+            bail!(
+                "round-trip check failed: client {} was written as {written_account:?} but is {account:?}",
+                account.client
+            );
+        }
+        if written_position != position {
+            bail!(
+                "round-trip check failed: client {} was written at position {} instead of {}",
+                account.client,
+                written_position + 1,
+                position + 1
+            );
+        }
+    }
+    if written_accounts.len() != expected_accounts {
+        bail!(
+            "round-trip check failed: the summary has {} account(s) instead of {expected_accounts}",
+            written_accounts.len()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{
+        data_structures::{NegativeStyle, SummaryRow, set_negative_style},
+        writers::{CsvSink, JsonSink, SummarySink, write_summary},
+    };
+
+    fn accounts() -> Vec<Account> {
+        vec![
+            Account {
+                client: 1,
+                available: Decimal::new(-12345, 4),
+                held: Decimal::new(5, 1),
+                total: Decimal::new(-7345, 4),
+                locked: false,
+            },
+            Account {
+                client: 2,
+                available: Decimal::from(100),
+                held: Decimal::ZERO,
+                total: Decimal::from(100),
+                locked: true,
+            },
+        ]
+    }
+
+    fn write_as(accounts: &[Account], format: OutputFormat, terminator: LineTerminator) -> Vec<u8> {
+        let mut written = Vec::new();
+        let sink: Box<dyn SummarySink> = match format {
+            OutputFormat::Csv => Box::new(CsvSink::new(&mut written, false, terminator)),
+            OutputFormat::Json => Box::new(JsonSink::new(&mut written, false)),
+        };
+        write_summary(vec![sink], accounts.iter().map(SummaryRow::Standard)).unwrap();
+        written
+    }
+
+    fn write(accounts: &[Account]) -> Vec<u8> {
+        write_as(accounts, OutputFormat::Csv, LineTerminator::Lf)
+    }
+
+    fn verify(accounts: &[Account], written: &[u8]) -> Result<()> {
+        verify_round_trip(accounts, written, OutputFormat::Csv, LineTerminator::Lf)
+    }
+
+    #[test]
+    fn test_round_trip_passes() {
+        let accounts = accounts();
+        verify(&accounts, &write(&accounts)).unwrap();
+
+        set_negative_style(NegativeStyle::Parens);
+        let written = write(&accounts);
+        set_negative_style(NegativeStyle::Minus);
+        verify(&accounts, &written).unwrap();
+
+        let written = write_as(&accounts, OutputFormat::Csv, LineTerminator::Crlf);
+        verify_round_trip(&accounts, &written, OutputFormat::Csv, LineTerminator::Crlf).unwrap();
+        let written = write_as(&accounts, OutputFormat::Json, LineTerminator::Lf);
+        verify_round_trip(&accounts, &written, OutputFormat::Json, LineTerminator::Lf).unwrap();
+    }
+
+    #[test]
+    fn test_round_trip_catches_wrong_layout() {
+        let accounts = accounts();
+        let err = verify_round_trip(
+            &accounts,
+            &write(&accounts),
+            OutputFormat::Csv,
+            LineTerminator::Crlf,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "round-trip check failed: the summary doesn't end its lines with Crlf"
+        );
+
+        let reversed = accounts.iter().rev().collect::<Vec<_>>();
+        let err = verify_round_trip(
+            reversed,
+            &write(&accounts),
+            OutputFormat::Csv,
+            LineTerminator::Lf,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "round-trip check failed: client 2 was written at position 2 instead of 1"
+        );
+
+        assert!(
+            verify_round_trip(
+                &accounts,
+                &write(&accounts),
+                OutputFormat::Json,
+                LineTerminator::Lf
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_round_trip_catches_corrupted_serializer() {
+        let accounts = accounts();
+        // a serializer that only keeps two fractional digits
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer
+            .write_record(["client", "available", "held", "total", "locked"])
+            .unwrap();
+        for account in &accounts {
+            writer
+                .write_record([
+                    account.client.to_string(),
+                    account.available.round_dp(2).to_string(),
+                    account.held.round_dp(2).to_string(),
+                    account.total.round_dp(2).to_string(),
+                    account.locked.to_string(),
+                ])
+                .unwrap();
+        }
+        let written = writer.into_inner().unwrap();
+
+        let err = verify(&accounts, &written).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("round-trip check failed: client 1 was written as"),
+            "unexpected error: {err}"
+        );
+        assert!(verify(&accounts[..1], &write(&accounts)).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use flate2::{Compression, write::GzEncoder};
+
+use crate::{
+    data_structures::SummaryRow,
+    writers::{FinishWrite, is_fifo},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Csv,
+    /// A JSON array of account objects
+    Json,
+}
+
+/// Line ending of the csv summary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LineTerminator {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`, as some spreadsheet tools on windows expect
+    Crlf,
+}
+
+impl From<LineTerminator> for csv::Terminator {
+    fn from(terminator: LineTerminator) -> Self {
+        match terminator {
+            LineTerminator::Lf => csv::Terminator::Any(b'\n'),
+            LineTerminator::Crlf => csv::Terminator::CRLF,
+        }
+    }
+}
+
+/// Where to write the summary to, given as `FORMAT:PATH` e.g. `json:summary.json` or just as
+/// `PATH`, in which case `.json` files are written as json and anything else as csv. Paths ending
+/// in `.gz` are written gzip compressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTarget {
+    pub format: OutputFormat,
+    pub path: PathBuf,
+}
+
+impl FromStr for OutputTarget {
+    type Err = String;
+
+    fn from_str(target: &str) -> Result<Self, Self::Err> {
+        // a single letter before the colon is rather a windows drive than a format
+        let (format, path) = match target.split_once(':') {
+            Some((format, path))
+                if format.len() > 1 && format.chars().all(|c| c.is_ascii_alphabetic()) =>
+            {
+                (Some(OutputFormat::from_str(format, true)?), path)
+            }
+            _ => (None, target),
+        };
+        if path.is_empty() {
+            return Err(format!("missing path in '{target}'"));
+        }
+
+        let path = PathBuf::from(path);
+        let format = format.unwrap_or_else(|| {
+            let uncompressed = match path.extension() {
+                Some(extension) if extension == "gz" => path.with_extension(""),
+                _ => path.clone(),
+            };
+            match uncompressed.extension() {
+                Some(extension) if extension == "json" => OutputFormat::Json,
+                _ => OutputFormat::Csv,
+            }
+        });
+        Ok(Self { format, path })
+    }
+}
+
+/// Destination of the account summary. Accounts are written one at a time so several sinks can
+/// be fed in a single pass over the accounts.
+pub trait SummarySink {
+    fn write_row(&mut self, row: &SummaryRow) -> Result<()>;
+    /// Make the rows written so far visible to the reader of the output.
+    fn flush(&mut self) -> Result<()>;
+    /// Write whatever the format needs after the last account and finish the underlying writer.
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+pub struct CsvSink<W: Write> {
+    writer: csv::Writer<W>,
+    flush_each_row: bool,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(writer: W, flush_each_row: bool, line_terminator: LineTerminator) -> Self {
+        Self {
+            writer: csv::WriterBuilder::new()
+                .has_headers(true)
+                .terminator(line_terminator.into())
+                .from_writer(writer),
+            flush_each_row,
+        }
+    }
+}
+
+impl<W: FinishWrite> SummarySink for CsvSink<W> {
+    fn write_row(&mut self, row: &SummaryRow) -> Result<()> {
+        self.writer.serialize(row)?;
+        if self.flush_each_row {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush().context("flush account summary as csv")
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.writer
+            .into_inner()
+            .map_err(|err| err.into_error())
+            .and_then(FinishWrite::finish)
+            .context("finish account summary as csv")
+    }
+}
+
+pub struct JsonSink<W: Write> {
+    writer: W,
+    accounts_written: usize,
+    flush_each_row: bool,
+}
+
+impl<W: Write> JsonSink<W> {
+    pub fn new(writer: W, flush_each_row: bool) -> Self {
+        Self {
+            writer,
+            accounts_written: 0,
+            flush_each_row,
+        }
+    }
+}
+
+impl<W: FinishWrite> SummarySink for JsonSink<W> {
+    fn write_row(&mut self, row: &SummaryRow) -> Result<()> {
+        let separator = if self.accounts_written == 0 { "[" } else { "," };
+        self.writer.write_all(separator.as_bytes())?;
+        serde_json::to_writer(&mut self.writer, row)?;
+        self.accounts_written += 1;
+        if self.flush_each_row {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush().context("flush account summary as json")
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        if self.accounts_written == 0 {
+            self.writer.write_all(b"[")?;
+        }
+        self.writer.write_all(b"]\n")?;
+        self.writer
+            .finish()
+            .context("finish account summary as json")
+    }
+}
+
+/// Open a sink on the target's file. A missing parent directory is only created with
+/// `create_dirs`, otherwise it's an error.
+pub fn create_sink(
+    target: &OutputTarget,
+    create_dirs: bool,
+    line_terminator: LineTerminator,
+) -> Result<Box<dyn SummarySink>> {
+    if let Some(dir) = target.path.parent()
+        && !dir.as_os_str().is_empty()
+        && !dir.exists()
+    {
+        if !create_dirs {
+            bail!(
+                "output directory '{}' does not exist, use --create-dirs to create it",
+                dir.display()
+            );
+        }
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create output directory '{}'", dir.display()))?;
+    }
+
+    let file = File::create(&target.path)
+        .with_context(|| format!("failed to create output file '{}'", target.path.display()))?;
+    let flush_each_row = is_fifo(&file);
+    let writer = BufWriter::new(file);
+
+    let is_gzip = target
+        .path
+        .extension()
+        .is_some_and(|extension| extension == "gz");
+    Ok(match (target.format, is_gzip) {
+        (OutputFormat::Csv, false) => {
+            Box::new(CsvSink::new(writer, flush_each_row, line_terminator))
+        }
+        (OutputFormat::Json, false) => Box::new(JsonSink::new(writer, flush_each_row)),
+        (OutputFormat::Csv, true) => Box::new(CsvSink::new(
+            GzEncoder::new(writer, Compression::default()),
+            flush_each_row,
+            line_terminator,
+        )),
+        (OutputFormat::Json, true) => Box::new(JsonSink::new(
+            GzEncoder::new(writer, Compression::default()),
+            flush_each_row,
+        )),
+    })
+}
+
+pub fn write_summary<'a, 's>(
+    mut sinks: Vec<Box<dyn SummarySink + 's>>,
+    rows: impl IntoIterator<Item = SummaryRow<'a>>,
+) -> Result<()> {
+    for row in rows {
+        for sink in sinks.iter_mut() {
+            sink.write_row(&row)?;
+        }
+    }
+    for sink in sinks {
+        sink.finish()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::data_structures::Account;
+
+    #[test]
+    fn test_parse_output_target() {
+        assert_eq!(
+            "json:out/summary.json".parse::<OutputTarget>(),
+            Ok(OutputTarget {
+                format: OutputFormat::Json,
+                path: PathBuf::from("out/summary.json"),
+            })
+        );
+        assert!("xml:summary.xml".parse::<OutputTarget>().is_err());
+        assert!("csv:".parse::<OutputTarget>().is_err());
+        assert_eq!(
+            "summary.json.gz".parse::<OutputTarget>(),
+            Ok(OutputTarget {
+                format: OutputFormat::Json,
+                path: PathBuf::from("summary.json.gz"),
+            })
+        );
+        assert_eq!(
+            "C:summary.txt".parse::<OutputTarget>(),
+            Ok(OutputTarget {
+                format: OutputFormat::Csv,
+                path: PathBuf::from("C:summary.txt"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_sink() {
+        let mut output = Vec::new();
+        let mut sink = Box::new(JsonSink::new(&mut output, false));
+        sink.write_row(&SummaryRow::Standard(&Account {
+            client: 1,
+            available: Decimal::new(15, 1),
+            held: Decimal::ZERO,
+            total: Decimal::new(15, 1),
+            locked: false,
+        }))
+        .unwrap();
+        sink.finish().unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[{\"client\":1,\"available\":\"1.5\",\"held\":\"0\",\"total\":\"1.5\",\"locked\":false}]\n"
+        );
+    }
+}
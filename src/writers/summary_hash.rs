@@ -0,0 +1,74 @@
+use sha2::{Digest, Sha256};
+
+use crate::data_structures::Account;
+
+/// Hex encoded SHA-256 over the summary rows sorted by client. The rows are written in a fixed
+/// format, every amount normalized and with a leading minus, so the hash only depends on the
+/// balances and not on the order the accounts are stored in or on the output formatting.
+pub fn summary_hash<'a>(accounts: impl IntoIterator<Item = &'a Account>) -> String {
+    let mut accounts = accounts.into_iter().collect::<Vec<_>>();
+    accounts.sort_by_key(|account| account.client);
+
+    let mut hasher = Sha256::new();
+    for account in accounts {
+        hasher.update(format!(
+            "{},{},{},{},{}\n",
+            account.client,
+            account.available.normalize(),
+            account.held.normalize(),
+            account.total.normalize(),
+            account.locked
+        ));
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::data_structures::{
+        DEFAULT_PRECISION, NegativeStyle, set_negative_style, set_precision,
+    };
+
+    fn account(client: u16, available: i64) -> Account {
+        Account {
+            client,
+            available: Decimal::from(available),
+            held: Decimal::ZERO,
+            total: Decimal::from(available),
+            locked: false,
+        }
+    }
+
+    #[test]
+    fn test_summary_hash() {
+        let accounts = [account(1, 10), account(2, 20)];
+        let reordered = [account(2, 20), account(1, 10)];
+        let changed = [account(1, 10), account(2, 21)];
+
+        let hash = summary_hash(&accounts);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, summary_hash(&reordered));
+        assert_ne!(hash, summary_hash(&changed));
+    }
+
+    #[test]
+    fn test_summary_hash_ignores_formatting() {
+        let accounts = [account(1, -10), account(2, 20)];
+        let hash = summary_hash(&accounts);
+
+        let mut rescaled = [account(1, -10), account(2, 20)];
+        rescaled[1].available.rescale(6);
+        set_precision(2);
+        set_negative_style(NegativeStyle::Parens);
+        assert_eq!(hash, summary_hash(&rescaled));
+        set_precision(DEFAULT_PRECISION);
+        set_negative_style(NegativeStyle::Minus);
+    }
+}
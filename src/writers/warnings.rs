@@ -0,0 +1,61 @@
+use std::io::Write;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum WarningsFormat {
+    /// Parse and record errors as text on the stdout, if `--log-errors` is set
+    #[default]
+    Human,
+    /// Every parse error and skipped transaction as a JSON object per line on the stderr
+    Json,
+}
+
+/// A row that couldn't be parsed or a transaction that wasn't applied.
+#[derive(Debug, Serialize)]
+pub struct Warning {
+    pub row: usize,
+    /// Only set when several transaction files are read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    pub tx: Option<u32>,
+    pub client: Option<u16>,
+    /// Machine readable identifier, e.g. `insufficient_funds`
+    pub reason: &'static str,
+    pub detail: String,
+}
+
+impl Warning {
+    pub fn write_json_line(&self, writer: &mut impl Write) -> Result<()> {
+        serde_json::to_writer(&mut *writer, self)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_line() {
+        let mut output = Vec::new();
+        Warning {
+            row: 3,
+            file: None,
+            tx: Some(2),
+            client: Some(1),
+            reason: "insufficient_funds",
+            detail: "the available funds don't cover the withdrawal".to_string(),
+        }
+        .write_json_line(&mut output)
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"row\":3,\"tx\":2,\"client\":1,\"reason\":\"insufficient_funds\",\"detail\":\"the available funds don't cover the withdrawal\"}\n"
+        );
+    }
+}
@@ -0,0 +1,1590 @@
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use rust_decimal::Decimal;
+use tempfile::NamedTempFile;
+
+fn input(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join(name)
+}
+
+fn write_input(contents: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(contents.as_bytes()).unwrap();
+    file
+}
+
+fn reader() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_account_transaction_reader"))
+}
+
+/// The summary rows in the order they were written, without the header.
+fn summary_rows(stdout: &[u8]) -> Vec<String> {
+    let stdout = String::from_utf8(stdout.to_vec()).expect("stdout to be utf-8");
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("client,available,held,total,locked"));
+    lines.map(str::to_string).collect()
+}
+
+#[test]
+fn test_fixed_width_input() {
+    let output = reader()
+        .arg(input("fixed_width_sample.txt"))
+        .args(["--input-format", "fixed"])
+        .args(["--field-widths", "type:10,client:5,tx:10,amount:15"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,-0.5,2,1.5,false", "2,2,0,2,false"]
+    );
+}
+
+#[test]
+fn test_fail_on_lock() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,2,2,5\n\
+         dispute,1,1,\n\
+         chargeback,1,1,\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--fail-on-lock")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,0,0,0,true", "2,5,0,5,false"]
+    );
+
+    let output = reader().arg(transactions.path()).output().unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_require_dispute_closure() {
+    let run = |input: &str| {
+        let transactions = write_input(input);
+        reader()
+            .arg(transactions.path())
+            .arg("--require-dispute-closure")
+            .output()
+            .unwrap()
+    };
+
+    let output = run("type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,1,2,5\n\
+         dispute,1,1,\n\
+         dispute,1,2,\n\
+         resolve,1,1,\n");
+    assert!(!output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,10,5,15,false"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with("client 1 has open disputes of tx 2\n"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("1 dispute(s) were neither resolved nor charged back"));
+
+    let output = run("type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,1,2,5\n\
+         dispute,1,1,\n\
+         dispute,1,2,\n\
+         resolve,1,1,\n\
+         chargeback,1,2,\n");
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,10,0,10,true"]);
+}
+
+#[test]
+fn test_emit_applied() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,1.23456\n\
+         withdrawal,1,2,5\n\
+         deposit,2,3,2.50\n\
+         refund,2,4,1\n\
+         dispute,2,3,\n\
+         resolve,2,99,\n",
+    );
+    let applied = NamedTempFile::new().unwrap();
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--emit-applied")
+        .arg(applied.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(applied.path()).unwrap(),
+        "type,client,tx,amount\n\
+         deposit,1,1,1.2346\n\
+         deposit,2,3,2.5\n\
+         dispute,2,3,\n"
+    );
+}
+
+#[test]
+fn test_emit_applied_lowercases_types() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         DEPOSIT,1,1,10\n\
+         Withdrawal,1,2,5\n",
+    );
+    let applied = NamedTempFile::new().unwrap();
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--emit-applied")
+        .arg(applied.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,5,0,5,false"]);
+    assert_eq!(
+        std::fs::read_to_string(applied.path()).unwrap(),
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         withdrawal,1,2,5\n"
+    );
+}
+
+#[test]
+fn test_emit_applied_keeps_application_order() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,3,1,5\n\
+         deposit,1,2,10\n\
+         deposit,2,3,20\n\
+         withdrawal,1,4,3\n\
+         dispute,3,1,\n\
+         withdrawal,2,5,100\n\
+         deposit,2,6,1\n\
+         resolve,3,1,\n",
+    );
+    let applied = NamedTempFile::new().unwrap();
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--emit-applied")
+        .arg(applied.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    // interleaved clients stay in input order, the rejected withdrawal is left out
+    assert_eq!(
+        std::fs::read_to_string(applied.path()).unwrap(),
+        "type,client,tx,amount\n\
+         deposit,3,1,5\n\
+         deposit,1,2,10\n\
+         deposit,2,3,20\n\
+         withdrawal,1,4,3\n\
+         dispute,3,1,\n\
+         deposit,2,6,1\n\
+         resolve,3,1,\n"
+    );
+}
+
+#[test]
+fn test_type_map() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         1,1,1,10\n\
+         2,1,2,4\n\
+         3,1,3,1\n",
+    );
+    let type_map = write_input(r#"{"1": "deposit", "2": "withdrawal"}"#);
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--type-map")
+        .arg(type_map.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,6,0,6,false"]);
+}
+
+#[test]
+fn test_complementary_byte_ranges() {
+    let mut contents = String::from("type,client,tx,amount\n");
+    for tx in 1..=50 {
+        let client = tx % 7;
+        contents.push_str(&format!("deposit,{client},{tx},{tx}.5\n"));
+        if tx % 3 == 0 {
+            contents.push_str(&format!("withdrawal,{client},{},1.25\n", tx + 1000));
+        }
+    }
+    let transactions = write_input(&contents);
+
+    let full = reader().arg(transactions.path()).output().unwrap();
+    assert!(full.status.success());
+
+    // split in the middle of a record
+    let split = contents.len() / 2 + 3;
+    let mut merged = BTreeMap::new();
+    for range in [format!("0:{split}"), format!("{split}:{}", contents.len())] {
+        let output = reader()
+            .arg(transactions.path())
+            .args(["--byte-range", &range])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        for row in summary_rows(&output.stdout) {
+            let fields: Vec<&str> = row.split(',').collect();
+            let total = merged
+                .entry(fields[0].parse::<u16>().unwrap())
+                .or_insert(Decimal::ZERO);
+            *total += fields[3].parse::<Decimal>().unwrap();
+        }
+    }
+
+    let expected: BTreeMap<u16, Decimal> = summary_rows(&full.stdout)
+        .iter()
+        .map(|row| {
+            let fields: Vec<&str> = row.split(',').collect();
+            (fields[0].parse().unwrap(), fields[3].parse().unwrap())
+        })
+        .collect();
+    assert_eq!(merged, expected);
+}
+
+#[test]
+fn test_multiple_outputs() {
+    let dir = tempfile::tempdir().unwrap();
+    let csv_path = dir.path().join("summary.csv");
+    let json_path = dir.path().join("summary.json");
+
+    let output = reader()
+        .arg(input("spec_example1.csv"))
+        .arg("--output")
+        .arg(format!("csv:{}", csv_path.display()))
+        .arg("--output")
+        .arg(format!("json:{}", json_path.display()))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        summary_rows(&std::fs::read(&csv_path).unwrap()),
+        vec!["1,1.5,0,1.5,false", "2,2,0,2,false"]
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&json_path).unwrap()).unwrap();
+    let mut accounts = json.as_array().unwrap().clone();
+    accounts.sort_by_key(|account| account["client"].as_u64());
+    assert_eq!(
+        accounts,
+        vec![
+            serde_json::json!({"client": 1, "available": "1.5", "held": "0", "total": "1.5", "locked": false}),
+            serde_json::json!({"client": 2, "available": "2", "held": "0", "total": "2", "locked": false}),
+        ]
+    );
+}
+
+#[test]
+fn test_gzip_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let gzip_path = dir.path().join("summary.csv.gz");
+
+    let output = reader()
+        .arg(input("spec_example1.csv"))
+        .arg("--output")
+        .arg(format!("csv:{}", gzip_path.display()))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let mut summary = Vec::new();
+    flate2::read::GzDecoder::new(std::fs::File::open(&gzip_path).unwrap())
+        .read_to_end(&mut summary)
+        .unwrap();
+    assert_eq!(
+        summary_rows(&summary),
+        vec!["1,1.5,0,1.5,false", "2,2,0,2,false"]
+    );
+}
+
+#[test]
+fn test_first_error_only() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,abc\n\
+         deposit,1,2,10\n\
+         deposit,x,3,1\n\
+         deposit,1,4,def\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--first-error-only")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert!(lines.next().unwrap().starts_with("error parsing row 1:"));
+    assert_eq!(lines.next(), Some("suppressed 2 further error(s)"));
+    let summary = lines.collect::<Vec<_>>().join("\n");
+    assert_eq!(summary_rows(summary.as_bytes()), vec!["1,10,0,10,false"]);
+}
+
+#[test]
+fn test_log_errors_of_parsing_and_recording() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,1,2,abc\n\
+         withdrawal,1,3,50\n\
+         depositt,1,4,5\n",
+    );
+    let parse_error = "error parsing row 2: CSV deserialize error: record 2 (line: 3, byte: 37): \
+                       Invalid decimal: unknown character";
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("-e")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{parse_error}\n\
+             error recording row 3: tx 3, the available funds don't cover the withdrawal\n\
+             error recording row 4: tx 4, unknown transaction type 'depositt'\n\
+             client,available,held,total,locked\n\
+             1,10,0,10,false\n"
+        )
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "row 4: rejected tx 4, unknown transaction type 'depositt'\n"
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--first-error-only")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{parse_error}\n\
+             suppressed 2 further error(s)\n\
+             client,available,held,total,locked\n\
+             1,10,0,10,false\n"
+        )
+    );
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+}
+
+#[test]
+fn test_stdin_without_path() {
+    let mut child = reader()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"type, client, tx, amount\ndeposit, 1, 1, 10\nwithdrawal, 1, 2, 2.5\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,7.5,0,7.5,false"]);
+}
+
+#[test]
+fn test_stream_interval() {
+    let mut child = reader()
+        .arg("-")
+        .arg("--stream-interval")
+        .arg("0.1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"type,client,tx,amount\n").unwrap();
+    for tx in 1..=3 {
+        writeln!(stdin, "deposit,1,{tx},1").unwrap();
+        stdin.flush().unwrap();
+        std::thread::sleep(Duration::from_millis(250));
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let snapshots = stdout
+        .split("client,available,held,total,locked\n")
+        .skip(1)
+        .collect::<Vec<_>>();
+    assert!(
+        snapshots.len() >= 2,
+        "expected several snapshots in {stdout:?}"
+    );
+    // every snapshot is complete and the last one covers the whole stream
+    assert_eq!(snapshots.last(), Some(&"1,3,0,3,false\n"));
+}
+
+#[test]
+fn test_wide_output() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,1,2,5.5\n\
+         withdrawal,1,3,3\n\
+         withdrawal,1,4,100\n\
+         deposit,2,5,1\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--wide")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(
+        lines.next(),
+        Some("client,available,held,total,locked,deposited,withdrawn")
+    );
+    let mut rows = lines.collect::<Vec<_>>();
+    rows.sort();
+    // the withdrawal exceeding the available funds isn't applied so it doesn't count
+    assert_eq!(
+        rows,
+        vec!["1,12.5,0,12.5,false,15.5,3", "2,1,0,1,false,1,0"]
+    );
+}
+
+#[test]
+fn test_benchmark() {
+    let output = reader().arg("--benchmark").arg("5000").output().unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let throughput = stdout
+        .lines()
+        .next()
+        .and_then(|line| line.split_once('('))
+        .and_then(|(_, rest)| rest.strip_suffix(" rows/sec)"))
+        .and_then(|rows_per_sec| rows_per_sec.parse::<f64>().ok())
+        .unwrap_or_else(|| panic!("no throughput in {stdout:?}"));
+    assert!(throughput > 0.0);
+}
+
+#[test]
+fn test_head_and_tail() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,1\n\
+         deposit,2,2,2\n\
+         deposit,3,3,3\n\
+         deposit,4,4,4\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--head")
+        .arg("2")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,1,0,1,false", "2,2,0,2,false"]
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--tail")
+        .arg("2")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["3,3,0,3,false", "4,4,0,4,false"]
+    );
+}
+
+#[test]
+fn test_json_warnings() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         withdrawal,1,2,20\n\
+         deposit,1,x,1\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--warnings-format")
+        .arg("json")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let warnings = String::from_utf8(output.stderr)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0]["row"], 2);
+    assert_eq!(warnings[0]["tx"], 2);
+    assert_eq!(warnings[0]["client"], 1);
+    assert_eq!(warnings[0]["reason"], "insufficient_funds");
+    assert!(warnings[0]["detail"].is_string());
+    assert_eq!(warnings[1]["row"], 3);
+    assert_eq!(warnings[1]["reason"], "parse_error");
+    assert!(warnings[1]["tx"].is_null());
+}
+
+#[test]
+fn test_finalize_on_chargeback() {
+    let mut child = reader()
+        .arg("-")
+        .arg("--finalize-on-chargeback")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+    stdin
+        .write_all(
+            b"type,client,tx,amount\n\
+              deposit,1,1,10\n\
+              deposit,2,2,5\n\
+              dispute,1,1,\n\
+              chargeback,1,1,\n",
+        )
+        .unwrap();
+    stdin.flush().unwrap();
+
+    // the locked account shows up while the input is still open
+    let mut finalized = String::new();
+    for _ in 0..2 {
+        std::io::BufRead::read_line(&mut stdout, &mut finalized).unwrap();
+    }
+    assert_eq!(
+        finalized,
+        "client,available,held,total,locked\n1,0,0,0,true\n"
+    );
+
+    stdin.write_all(b"deposit,2,3,1\n").unwrap();
+    drop(stdin);
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest).unwrap();
+    assert!(child.wait().unwrap().success());
+    assert_eq!(rest, "2,6,0,6,false\n");
+}
+
+#[test]
+fn test_finalize_on_chargeback_then_unlock() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,50\n\
+         deposit,1,2,20\n\
+         dispute,1,2,\n\
+         chargeback,1,2,\n\
+         unlock,1,3,\n\
+         deposit,1,4,100\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--finalize-on-chargeback")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    // the unlocked account is written again with its final balances
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "client,available,held,total,locked\n\
+         1,50,0,50,true\n\
+         1,150,0,150,false\n"
+    );
+}
+
+#[test]
+fn test_disputed_clients_only() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,2,2,20\n\
+         deposit,3,3,30\n\
+         dispute,2,2,\n\
+         resolve,2,2,\n\
+         dispute,3,1,\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--disputed-clients-only")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    // the dispute of client 3 references a transaction of client 1 so it's not applied
+    assert_eq!(summary_rows(&output.stdout), vec!["2,20,0,20,false"]);
+}
+
+#[test]
+fn test_explain_rejections() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         withdrawal,1,2,50\n\
+         withdrawal,1,3,20\n\
+         dispute,1,9,\n\
+         deposit,2,4,5\n\
+         dispute,2,4,\n\
+         chargeback,2,4,\n\
+         deposit,2,5,1\n\
+         deposit,x,6,1\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--explain-rejections")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "5 row(s) rejected\n\
+         insufficient_funds      2\n\
+         account_locked          1\n\
+         parse_error             1\n\
+         unknown_dispute_target  1\n"
+    );
+}
+
+#[test]
+fn test_create_dirs() {
+    let transactions = write_input("type,client,tx,amount\ndeposit,1,1,10\n");
+    let dir = tempfile::tempdir().unwrap();
+    let summary = dir.path().join("nested/out/summary.csv");
+    let target = format!("csv:{}", summary.display());
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--output")
+        .arg(&target)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("does not exist, use --create-dirs to create it"),
+        "unexpected error: {stderr}"
+    );
+    assert!(!dir.path().join("nested").exists());
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--output")
+        .arg(&target)
+        .arg("--create-dirs")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(summary).unwrap(),
+        "client,available,held,total,locked\n1,10,0,10,false\n"
+    );
+}
+
+#[test]
+fn test_tolerate_n_errors() {
+    let run = |input: &str| {
+        let transactions = write_input(input);
+        reader()
+            .arg(transactions.path())
+            .arg("--tolerate-n-errors")
+            .arg("2")
+            .output()
+            .unwrap()
+    };
+
+    let output = run("type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,x,2,1\n\
+         withdrawal,1,3,50\n\
+         deposit,1,4,5\n");
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,15,0,15,false"]);
+
+    let output = run("type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,x,2,1\n\
+         withdrawal,1,3,50\n\
+         dispute,1,9,\n\
+         deposit,1,4,5\n");
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("aborting after 3 erroneous rows, only 2 are tolerated")
+    );
+}
+
+#[test]
+fn test_frozen_ratio() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,70\n\
+         deposit,1,2,30\n\
+         dispute,1,2,\n\
+         deposit,2,3,10\n\
+         withdrawal,2,4,10\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--with-frozen-ratio")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines().collect::<Vec<_>>();
+    lines[1..].sort();
+    assert_eq!(
+        lines,
+        vec![
+            "client,available,held,total,locked,frozen_ratio",
+            "1,70,30,100,false,0.3000",
+            "2,0,0,0,false,0.0000"
+        ]
+    );
+}
+
+#[test]
+fn test_pipe_to() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         withdrawal,1,2,50\n\
+         dispute,1,1,\n",
+    );
+    let forwarded = NamedTempFile::new().unwrap();
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--pipe-to")
+        .arg(format!("cat > '{}'", forwarded.path().display()))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,0,10,10,false"]);
+    assert_eq!(
+        std::fs::read_to_string(forwarded.path()).unwrap(),
+        "type,client,tx,amount\ndeposit,1,1,10\ndispute,1,1,\n"
+    );
+
+    // a command that doesn't read anything doesn't fail the run
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--pipe-to")
+        .arg("exit 0")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,0,10,10,false"]);
+}
+
+#[test]
+fn test_min_balance() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,100\n\
+         deposit,2,2,5\n\
+         deposit,3,3,5\n\
+         dispute,3,3,\n\
+         chargeback,3,3,\n\
+         deposit,4,4,50\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--min-balance")
+        .arg("50")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,100,0,100,false", "4,50,0,50,false"]
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--min-balance")
+        .arg("50")
+        .arg("--always-show-locked")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,100,0,100,false", "3,0,0,0,true", "4,50,0,50,false"]
+    );
+}
+
+#[test]
+fn test_dead_letter() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,1,x,5\n\
+         withdrawal,1,3,20.5\n\
+         withdrawal,1,4,2\n",
+    );
+    let dead_letter = NamedTempFile::new().unwrap();
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--dead-letter")
+        .arg(dead_letter.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,8,0,8,false"]);
+    let dead_letters = std::fs::read_to_string(dead_letter.path()).unwrap();
+    let mut lines = dead_letters.lines();
+    assert_eq!(
+        lines.next(),
+        Some("row,file,type,client,tx,amount,reason,detail")
+    );
+    assert!(lines.next().unwrap().starts_with("2,,,,,,parse_error,"));
+    assert_eq!(
+        lines.next(),
+        Some(
+            "3,,withdrawal,1,3,20.5,insufficient_funds,the available funds don't cover the withdrawal"
+        )
+    );
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn test_resolutions() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,1,2,20\n\
+         deposit,2,3,30\n\
+         dispute,1,1,\n\
+         dispute,1,2,\n\
+         dispute,2,3,\n",
+    );
+    let resolutions = write_input("tx\n1\n2\n3\n4\n");
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--resolutions")
+        .arg(resolutions.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,30,0,30,false", "2,30,0,30,false"]
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "resolution of tx 4 not applied, the client has no deposit or withdrawal with this id\n"
+    );
+}
+
+#[test]
+fn test_output_path() {
+    let transactions = write_input("type,client,tx,amount\ndeposit,1,1,10\ndeposit,x,2,1\n");
+    let summary = NamedTempFile::new().unwrap();
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("-o")
+        .arg(summary.path())
+        .arg("--log-errors")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(summary.path()).unwrap(),
+        "client,available,held,total,locked\n1,10,0,10,false\n"
+    );
+    // errors still go to the stdout, the summary file only holds the summary
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("error parsing row 2:"), "{stdout}");
+    assert_eq!(stdout.lines().count(), 1);
+}
+
+#[test]
+fn test_line_terminator() {
+    let transactions = write_input("type,client,tx,amount\ndeposit,1,1,10\n");
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--line-terminator", "crlf"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "client,available,held,total,locked\r\n1,10,0,10,false\r\n"
+    );
+
+    let output = reader().arg(transactions.path()).output().unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "client,available,held,total,locked\n1,10,0,10,false\n"
+    );
+}
+
+#[test]
+fn test_multiple_files() {
+    let day1 = write_input("type,client,tx,amount\ndeposit,1,1,10\ndeposit,2,2,5\n");
+    let day2 = write_input("type,client,tx,amount\ndispute,1,1,\nwithdrawal,x,3,1\n");
+
+    let output = reader()
+        .arg(day1.path())
+        .arg(day2.path())
+        .arg("--log-errors")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with(&format!(
+            "error parsing row 2 of '{}':",
+            day2.path().display()
+        )),
+        "{stdout}"
+    );
+    let summary = stdout.split_once('\n').unwrap().1;
+    assert_eq!(
+        summary_rows(summary.as_bytes()),
+        vec!["1,0,10,10,false", "2,5,0,5,false"]
+    );
+}
+
+#[test]
+fn test_multiple_files_in_reports() {
+    let day1 = write_input("type,client,tx,amount\ndeposit,1,1,10\n");
+    let day2 = write_input("type,client,tx,amount\ndeposit,1,1,5\nwithdrawal,1,2,50\n");
+    let dead_letter = NamedTempFile::new().unwrap();
+
+    let output = reader()
+        .arg(day1.path())
+        .arg(day2.path())
+        .args(["--report-duplicate-tx", "--warnings-format", "json"])
+        .arg("--dead-letter")
+        .arg(dead_letter.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let (day1, day2) = (day1.path().display(), day2.path().display());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("\"row\":2,\"file\":\"{day2}\",\"tx\":2,")),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains(&format!(
+            "tx 1 appears in rows 1 of '{day1}', 1 of '{day2}'"
+        )),
+        "{stderr}"
+    );
+    let dead_letters = std::fs::read_to_string(dead_letter.path()).unwrap();
+    assert!(
+        dead_letters.contains(&format!("\n2,{day2},withdrawal,1,2,50,insufficient_funds,")),
+        "{dead_letters}"
+    );
+}
+
+#[test]
+fn test_json_lines_input() {
+    let transactions = write_input(
+        "{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":\"10\"}\n\
+         {\"type\":\"deposit\",\"client\":1\n\
+         {\"type\":\"withdrawal\",\"client\":1,\"tx\":2,\"amount\":\"2.5\"}\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--input-format", "jsonl", "--log-errors"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let (error, summary) = stdout.split_once('\n').unwrap();
+    assert!(
+        error.starts_with("error parsing row 2: failed to parse json line"),
+        "{error}"
+    );
+    assert_eq!(summary_rows(summary.as_bytes()), vec!["1,7.5,0,7.5,false"]);
+}
+
+#[test]
+fn test_csv_options_with_json_input() {
+    let transactions =
+        write_input("{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":\"10\"}\n");
+
+    for (format, flag) in [
+        ("jsonl", ["--trim", "none"]),
+        ("json-array", ["--quote", "'"]),
+        ("jsonl", ["--null-tokens", "-"]),
+    ] {
+        let output = reader()
+            .arg(transactions.path())
+            .args(["--input-format", format])
+            .args(flag)
+            .output()
+            .unwrap();
+
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            stderr.contains(&format!(
+                "{} can't be used with --input-format {format}",
+                flag[0]
+            )),
+            "{stderr}"
+        );
+    }
+
+    // the defaults of the csv options don't count as passed
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--input-format", "jsonl"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,10,0,10,false"]);
+}
+
+#[test]
+fn test_output_format_json() {
+    let transactions = write_input("type,client,tx,amount\ndeposit,1,1,1.5\n");
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--output-format", "json"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([{"client": 1, "available": "1.5", "held": "0", "total": "1.5", "locked": false}])
+    );
+}
+
+#[test]
+fn test_summary_ordered_by_client() {
+    let transactions = write_input(
+        "type,client,tx,amount\ndeposit,3,1,1\ndeposit,1,2,1\ndeposit,20,3,1\ndeposit,2,4,1\n",
+    );
+
+    for _ in 0..3 {
+        let output = reader().arg(transactions.path()).output().unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "client,available,held,total,locked\n\
+             1,1,0,1,false\n\
+             2,1,0,1,false\n\
+             3,1,0,1,false\n\
+             20,1,0,1,false\n"
+        );
+    }
+}
+
+#[test]
+fn test_json_array_input() {
+    let transactions = write_input(
+        r#"[
+            {"type": "deposit", "client": 1, "tx": 1, "amount": "10"},
+            {"type": "deposit", "client": 2, "tx": 2, "amount": "3"},
+            {"type": "dispute", "client": 1, "tx": 1}
+        ]"#,
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--input-format", "json-array"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,0,10,10,false", "2,3,0,3,false"]
+    );
+}
+
+#[test]
+fn test_sort_disputes_desc() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,5\n\
+         deposit,2,2,5\n\
+         deposit,2,3,5\n\
+         deposit,3,4,5\n\
+         dispute,2,2,\n\
+         dispute,2,3,\n\
+         dispute,3,4,\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--sort", "disputes-desc"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "client,available,held,total,locked\n\
+         2,0,10,10,false\n\
+         3,0,5,5,false\n\
+         1,5,0,5,false\n"
+    );
+}
+
+#[test]
+fn test_stats() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         withdrawal,1,2,20\n\
+         dispute,1,1,\n\
+         resolve,1,1,\n\
+         deposit,x,3,1\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--stats")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,10,0,10,false"]);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rows          5\n\
+         deposits      1\n\
+         withdrawals   0\n\
+         disputes      1\n\
+         resolves      1\n\
+         chargebacks   0\n\
+         unlocks       0\n\
+         parse errors  1\n\
+         skipped       1\n"
+    );
+}
+
+#[test]
+fn test_min_unit() {
+    let transactions = write_input("type,client,tx,amount\ndeposit,1,1,10.001\ndeposit,1,2,5\n");
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--min-unit", "0.01"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,5,0,5,false"]);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "row 1: rejected tx 1, the amount 10.001 isn't a multiple of the minimum unit 0.01\n"
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--min-unit", "0.01", "--min-unit-mode", "round"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,15,0,15,false"]);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "amount 10.001 of tx 1 for client 1 was rounded to 10\n"
+    );
+}
+
+#[test]
+fn test_unknown_type() {
+    let transactions = write_input("type,client,tx,amount\ndepositt,1,1,10\ndeposit,2,2,5\n");
+
+    let output = reader().arg(transactions.path()).output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["2,5,0,5,false"]);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "row 1: rejected tx 1, unknown transaction type 'depositt'\n"
+    );
+}
+
+#[test]
+fn test_events() {
+    let transactions =
+        write_input("type,client,tx,amount\ndeposit,1,1,10.5\nwithdrawal,1,2,20\ndispute,1,1,\n");
+    let events = NamedTempFile::new().unwrap();
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--events")
+        .arg(events.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let events = std::fs::read_to_string(events.path())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        vec![
+            serde_json::json!({"client": 1, "tx": 1, "type": "deposit", "delta_available": "10.5", "delta_held": "0", "delta_total": "10.5"}),
+            serde_json::json!({"client": 1, "tx": 1, "type": "dispute", "delta_available": "-10.5", "delta_held": "10.5", "delta_total": "0"}),
+        ]
+    );
+}
+
+#[test]
+fn test_reject_dispute_amounts() {
+    let transactions = write_input("type,client,tx,amount\ndeposit,1,1,10\ndispute,1,1,10.0\n");
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--reject-dispute-amounts")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,10,0,10,false"]);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "row 2: rejected tx 1, disputes, resolves and chargebacks have no amount but got 10\n"
+    );
+}
+
+#[test]
+fn test_max_file_bytes() {
+    let transactions = write_input("type,client,tx,amount\ndeposit,1,1,10\n");
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--max-file-bytes", "10", "--log-errors"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("has 37 bytes, more than the --max-file-bytes limit of 10"),
+        "{stderr}"
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--max-file-bytes", "37"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,10,0,10,false"]);
+}
+
+#[test]
+fn test_on_overflow_abort() {
+    let input = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,79228162514264337593543950330\n\
+         deposit,1,2,10\n",
+    );
+
+    let output = reader()
+        .arg(input.path())
+        .args(["--on-overflow", "abort"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("row 2: tx 2 overflows the balances of client 1")
+    );
+}
+
+#[test]
+fn test_tx_scope() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,2,1,20\n",
+    );
+
+    let output = reader().arg(transactions.path()).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(summary_rows(&output.stdout), vec!["1,10,0,10,false"]);
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--tx-scope", "client"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,10,0,10,false", "2,20,0,20,false"]
+    );
+}
+
+#[test]
+fn test_round_trip_check() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         deposit,2,2,20.5\n\
+         dispute,2,2,\n",
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let csv_path = dir.path().join("summary.csv");
+    let json_path = dir.path().join("summary.json.gz");
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--round-trip-check", "--sort", "disputes-desc"])
+        .args(["--line-terminator", "crlf"])
+        .arg("-o")
+        .arg(&csv_path)
+        .arg("-o")
+        .arg(&json_path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(&csv_path).unwrap(),
+        "client,available,held,total,locked\r\n2,0,20.5,20.5,false\r\n1,10,0,10,false\r\n"
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .args(["--round-trip-check", "--output-format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"[{\"client\":1,"));
+}
+
+#[test]
+fn test_summary_hash() {
+    let transactions = write_input(
+        "type,client,tx,amount\n\
+         deposit,1,1,10\n\
+         withdrawal,1,2,8.5\n\
+         dispute,1,1,\n\
+         deposit,2,3,100\n",
+    );
+    let hash = |args: &[&str]| {
+        let output = reader()
+            .arg(transactions.path())
+            .arg("--summary-hash")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    let full = hash(&[]);
+    assert!(full.starts_with("summary sha256: "), "{full}");
+    // the display flags don't change the balances
+    assert_eq!(
+        full,
+        hash(&["--precision", "6", "--negative-style", "parens"])
+    );
+    // only the rows that are written are hashed
+    assert_ne!(full, hash(&["--min-balance", "50"]));
+}
+
+#[test]
+fn test_sort_by_timestamp() {
+    let transactions = write_input(
+        "type,client,tx,amount,timestamp\n\
+         dispute,1,1,,30\n\
+         withdrawal,1,2,8,20\n\
+         deposit,1,1,10,10\n\
+         deposit,2,3,5,20\n\
+         withdrawal,2,4,5,20\n",
+    );
+
+    let output = reader()
+        .arg(transactions.path())
+        .arg("--sort-by-timestamp")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,-8,10,2,false", "2,0,0,0,false"]
+    );
+
+    // in file order the dispute and withdrawal come before the deposit they depend on
+    let output = reader().arg(transactions.path()).output().unwrap();
+    assert_eq!(
+        summary_rows(&output.stdout),
+        vec!["1,10,0,10,false", "2,0,0,0,false"]
+    );
+}
+
+#[cfg(unix)]
+mod fifo {
+    use std::{
+        fs::File,
+        io::{BufRead, BufReader},
+        thread,
+    };
+
+    use super::*;
+
+    fn make_fifo() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let fifo = dir.path().join("summary.fifo");
+        let status = Command::new("mkfifo").arg(&fifo).status().unwrap();
+        assert!(status.success());
+        (dir, fifo)
+    }
+
+    #[test]
+    fn test_write_summary_to_fifo() {
+        let (_dir, fifo) = make_fifo();
+        let reader_fifo = fifo.clone();
+        let fifo_reader = thread::spawn(move || {
+            let mut summary = String::new();
+            File::open(reader_fifo)
+                .unwrap()
+                .read_to_string(&mut summary)
+                .unwrap();
+            summary
+        });
+
+        let status = reader()
+            .arg(input("spec_example1.csv"))
+            .stdout(File::options().write(true).open(&fifo).unwrap())
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+        let summary = fifo_reader.join().unwrap();
+        assert_eq!(
+            summary_rows(summary.as_bytes()),
+            vec!["1,1.5,0,1.5,false", "2,2,0,2,false"]
+        );
+    }
+
+    #[test]
+    fn test_fifo_reader_disconnecting() {
+        let mut contents = String::from("type,client,tx,amount\n");
+        for client in 1..=20_000 {
+            contents.push_str(&format!("deposit,{client},{client},1.2345\n"));
+        }
+        let transactions = write_input(&contents);
+
+        let (_dir, fifo) = make_fifo();
+        let reader_fifo = fifo.clone();
+        let fifo_reader = thread::spawn(move || {
+            // only read the header and then hang up on the writer
+            let mut header = String::new();
+            BufReader::new(File::open(reader_fifo).unwrap())
+                .read_line(&mut header)
+                .unwrap();
+            header
+        });
+
+        let output = reader()
+            .arg(transactions.path())
+            .stdout(File::options().write(true).open(&fifo).unwrap())
+            .stderr(Stdio::piped())
+            .output()
+            .unwrap();
+
+        assert_eq!(
+            fifo_reader.join().unwrap(),
+            "client,available,held,total,locked\n"
+        );
+        assert!(output.status.success());
+        assert!(output.stderr.is_empty());
+    }
+}